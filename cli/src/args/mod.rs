@@ -1,44 +1,108 @@
 use {
-    num_bigint::BigUint,
+    mpt_core::proof::CompactProof,
     serde::{Deserialize, Serialize},
-    std::fs,
+    std::{env, fs},
 };
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MptParams {
     pub root: String,
+    #[serde(default)]
     pub key: String,
+    /// Transaction index in the block. When set, the program derives the
+    /// trie key itself and `key` is ignored.
+    #[serde(default)]
+    pub index: Option<u64>,
     pub proof: Vec<String>,
 }
 
 const MPT_PATH: &str = "cli/proof/mpt.json";
 
 impl MptParams {
+    /// Loads the proof file given with `--proof <path>`, `cli/proof/mpt.json`
+    /// by default. A `.bin` file holds a compact binary proof.
     pub fn load() -> Self {
-        let contents = fs::read_to_string(MPT_PATH).unwrap();
+        let args: Vec<String> = env::args().collect();
+        let path = args
+            .iter()
+            .position(|arg| arg == "--proof")
+            .and_then(|i| args.get(i + 1))
+            .map_or(MPT_PATH, String::as_str);
+
+        if path.ends_with(".bin") {
+            let contents = fs::read(path).unwrap();
+            let proof = CompactProof::decode(&contents).expect("invalid compact proof");
+            return MptParams {
+                root: to_hex(proof.root.as_bytes()),
+                key: to_hex(proof.key),
+                index: None,
+                proof: proof.nodes.iter().map(|node| to_hex(node)).collect(),
+            };
+        }
+        let contents = fs::read_to_string(path).unwrap();
         serde_json::from_str(&contents).unwrap()
     }
 
     pub fn key(&self) -> Vec<u8> {
-        let big_int = BigUint::parse_bytes(self.key.as_bytes(), 16).unwrap();
-        big_int.to_bytes_be()
+        from_hex(&self.key)
     }
 
     pub fn root(&self) -> [u8; 32] {
-        let big_int = BigUint::parse_bytes(self.root.as_bytes(), 16).unwrap();
-        big_int
-            .to_bytes_be()
+        from_hex(&self.root)
             .try_into()
             .expect("Vector has incorrect length")
-        // self.root.to_owned()
     }
 
     pub fn proof(&self) -> Vec<Vec<u8>> {
-        let mut proofs = Vec::new();
-        for p in self.proof.clone().into_iter() {
-            let value = BigUint::parse_bytes(p.as_bytes(), 16).unwrap();
-            proofs.push(value.to_bytes_be());
-        }
-        proofs
+        self.proof.iter().map(|p| from_hex(p)).collect()
     }
 }
+
+/// A block's receipts, or transactions, in order, with the root they should
+/// hash to.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ReceiptsParams {
+    pub root: String,
+    pub receipts: Vec<String>,
+}
+
+impl ReceiptsParams {
+    /// Loads the receipts file given with `--receipts <path>`, if any.
+    pub fn load() -> Option<Self> {
+        let args: Vec<String> = env::args().collect();
+        let path = args
+            .iter()
+            .position(|arg| arg == "--receipts")
+            .and_then(|i| args.get(i + 1))?;
+        let contents = fs::read_to_string(path).unwrap();
+        Some(serde_json::from_str(&contents).unwrap())
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        from_hex(&self.root)
+            .try_into()
+            .expect("Vector has incorrect length")
+    }
+
+    pub fn receipts(&self) -> Vec<Vec<u8>> {
+        self.receipts.iter().map(|r| from_hex(r)).collect()
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Decodes a hex string byte for byte, keeping leading zero bytes.
+fn from_hex(hex: &str) -> Vec<u8> {
+    let hex = hex.trim_start_matches("0x");
+    assert!(
+        hex.len().is_multiple_of(2),
+        "odd-length hex string: {}",
+        hex
+    );
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("invalid hex string"))
+        .collect()
+}
@@ -1,11 +1,23 @@
 use {
-    anchor_lang::{prelude::borsh, Discriminator},
+    anchor_lang::{prelude::borsh, AnchorDeserialize, Discriminator},
     anchor_mpt_demo::{
-        instruction::{AppendProof, InitProof, ValidateMpt, ViewProof},
-        InitProofParams, ID,
+        instruction::{
+            AppendProof, AppendValues, InitProof, InitValues, TraceProof, ValidateLeafHash,
+            ValidateMpt, ValidateMptByIndex, ValidateReceiptsRoot, ViewProof,
+        },
+        InitProofParams, ProofTraceInfo, ID, VALUES_SEED,
     },
+    base64::{engine::general_purpose::STANDARD, Engine},
     dotenv::dotenv,
-    eyre::Result,
+    eyre::{eyre, Result},
+    mpt_core::{
+        builder::EthTrieBuilder,
+        hasher::{Keccak, TrieHasher},
+        node::Node,
+        proof::CompactProof,
+        trie::{index_key, EthTrie},
+        H256,
+    },
     solana_rpc_client::rpc_client,
     solana_sdk::{
         // compute_budget::ComputeBudgetInstruction,
@@ -19,7 +31,7 @@ use {
 };
 
 mod args;
-use args::MptParams;
+use args::{MptParams, ReceiptsParams};
 
 // const RPC_ADDR: &str = "https://api.devnet.solana.com";
 const RPC_ADDR: &str = "http://127.0.0.1:8899";
@@ -34,13 +46,11 @@ const TX_MAX_SIZE: usize = 996;
 // 998 1648
 fn main() -> Result<()> {
     dotenv().ok();
-    let proof = MptParams::load();
-    // println!("proof: {:?}", proof.proof());
     let program_id = Pubkey::from_str(ID.to_string().as_str()).unwrap();
 
     let private_key_str = env::var("PRIVATE_KEY").expect("PRIVATE_KEY not found in .env file");
     let private_key_bytes: Vec<u8> = private_key_str
-        .trim_matches(|c: char| !c.is_digit(10) && c != ',')
+        .trim_matches(|c: char| !c.is_ascii_digit() && c != ',')
         .split(',') // 以逗号分割字符串
         .filter_map(|s| s.parse::<u8>().ok())
         .collect();
@@ -48,80 +58,89 @@ fn main() -> Result<()> {
     let signer = Keypair::from_bytes(&private_key_bytes).unwrap();
     let client = rpc_client::RpcClient::new(RPC_ADDR);
 
+    // With `--receipts <path>` a block's receipts go up in full instead of a
+    // proof, and the program checks they hash to the block's root.
+    if let Some(receipts) = ReceiptsParams::load() {
+        return validate_receipts(&client, &signer, program_id, &receipts);
+    }
+
+    let proof = MptParams::load();
+    // println!("proof: {:?}", proof.proof());
+
+    // Check the proof with the verifier the program runs before paying for
+    // the uploads.
+    let key = match proof.index {
+        Some(index) => index_key(index),
+        None => proof.key(),
+    };
+    let mut nodes = proof.proof();
+    EthTrie::verify_proof(H256(proof.root()), &key, &nodes)?
+        .ok_or_else(|| eyre!("key not found in trie"))?;
+
+    // With `--leaf-hash` the leaf, the last proof node, stays off-chain and
+    // the program checks the rest of the proof against its hash. A leaf
+    // embedded in its parent is not a proof node and cannot be left out.
+    let leaf_hash = if env::args().any(|arg| arg == "--leaf-hash") {
+        let leaf = nodes.pop().ok_or_else(|| eyre!("empty proof"))?;
+        let Node::Leaf(leaf_node) = Node::decode(&leaf)? else {
+            return Err(eyre!("the leaf is embedded in its parent node"));
+        };
+        let rest = leaf_node.key.len() - 1;
+        let leaf_hash = Keccak::hash(&leaf);
+        EthTrie::verify_leaf_hash(H256(proof.root()), &key, rest, leaf_hash, &nodes)?;
+        Some((rest as u16, leaf_hash.to_fixed_bytes()))
+    } else {
+        None
+    };
+
+    // With `--compact` the proof goes up as a single compact item, which
+    // the program decodes when verifying.
+    let items = if env::args().any(|arg| arg == "--compact") {
+        vec![CompactProof::encode(H256(proof.root()), &key, &nodes)?]
+    } else {
+        nodes
+    };
+
     let mut len: usize = 4;
-    for v in proof.proof() {
+    for v in &items {
         len += 4 + v.len();
     }
     let hash_root = proof.root();
     let seeds: &[&[u8]] = &[b"MptProof", hash_root.as_slice()];
 
-    let (mpt_account, _) = Pubkey::find_program_address(&seeds, &program_id);
+    let (mpt_account, _) = Pubkey::find_program_address(seeds, &program_id);
 
     let init_ix =
         init_proof_instruction(program_id, signer.pubkey(), mpt_account, &proof, len as u32);
-    send_transaction(&client, &signer, &vec![init_ix], "init_proof_instruction");
-
-    let mut data = Vec::new();
-    let mut remaining_length = TX_MAX_SIZE;
-    let mut is_merge = false;
-    for d in proof.proof() {
-        let cur_size = d.len();
-        let mut remaining_data = Vec::new();
-        if remaining_length > 0 {
-            if remaining_length > cur_size {
-                data.push(d);
-                remaining_length -= cur_size;
-            } else {
-                // let l = cur_size - remaining_length;
-                data.push(d[..remaining_length].to_vec());
-                remaining_data = d[remaining_length..].to_vec();
-                remaining_length = 0;
-            }
-        }
-
-        if remaining_length > TX_MAX_SIZE {
-            //错误
-            // return Err("错误");
-            println!("错误----remaining_length :{}", remaining_length);
-        }
-
-        if remaining_length == 0 {
-            // for (k, v) in data.iter().enumerate() {
-            //     println!("k: {}, v: {}", k, to_hex_string(v));
-            // }
-
-            let ix = append_proof_instruction(program_id, mpt_account, data.clone(), is_merge);
-            send_transaction(&client, &signer, &vec![ix], "append_proof ");
+    send_transaction(&client, &signer, &[init_ix], "init_proof_instruction");
 
-            data.clear();
-            remaining_length = TX_MAX_SIZE;
-            is_merge = false;
-        }
-
-        if remaining_data.len() > 0 {
-            //todo 有可能 remaining_length 大于 TX_MAX_SIZE
+    upload_proof(
+        &client,
+        &signer,
+        program_id,
+        mpt_account,
+        &items,
+        append_proof_instruction,
+    );
 
-            remaining_length -= remaining_data.len();
-            data.push(remaining_data);
-            is_merge = true;
-        }
-    }
+    // let ix = view_instruction(program_id, mpt_account);
+    // send_transaction(&client, &signer, &[ix], "view ");
 
-    // 最后一个循环可能还有数据
-    if data.len() > 0 {
-        // for (k, v) in data.iter().enumerate() {
-        //     println!("k: {}, v: {}", k, to_hex_string(v));
-        // }
-        let ix = append_proof_instruction(program_id, mpt_account, data.clone(), is_merge);
-        send_transaction(&client, &signer, &vec![ix], "append_proof ");
+    if env::args().any(|arg| arg == "--trace") {
+        let ix = trace_instruction(program_id, mpt_account, key);
+        print_trace(&client, &signer, &[ix]);
+        return Ok(());
     }
 
-    // let ix = view_instruction(program_id, mpt_account);
-    // send_transaction(&client, &signer, &vec![ix], "view ");
-
     // let uc_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(500_0000);
-    let ix = verify_instruction(program_id, mpt_account, &proof);
-    send_transaction(&client, &signer, &vec![ix], "verify");
+    let ix = match (leaf_hash, proof.index) {
+        (Some((rest, leaf_hash)), _) => {
+            leaf_hash_instruction(program_id, mpt_account, key, rest, leaf_hash)
+        }
+        (None, Some(index)) => verify_by_index_instruction(program_id, mpt_account, index),
+        (None, None) => verify_instruction(program_id, mpt_account, &proof),
+    };
+    send_transaction(&client, &signer, &[ix], "verify");
     Ok(())
 }
 
@@ -208,6 +227,211 @@ pub fn verify_instruction(
     }
 }
 
+pub fn verify_by_index_instruction(
+    program_id: Pubkey,
+    mpt_account: Pubkey,
+    index: u64,
+) -> Instruction {
+    let instruction_data = ValidateMptByIndex { index };
+    let mut data = borsh::to_vec(&instruction_data).unwrap();
+
+    let account_metas = vec![AccountMeta::new(mpt_account, false)];
+
+    data.splice(0..0, ValidateMptByIndex::DISCRIMINATOR.iter().cloned());
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data,
+    }
+}
+
+pub fn leaf_hash_instruction(
+    program_id: Pubkey,
+    mpt_account: Pubkey,
+    key: Vec<u8>,
+    rest: u16,
+    leaf_hash: [u8; 32],
+) -> Instruction {
+    let instruction_data = ValidateLeafHash {
+        key,
+        rest,
+        leaf_hash,
+    };
+    let mut data = borsh::to_vec(&instruction_data).unwrap();
+
+    let account_metas = vec![AccountMeta::new(mpt_account, false)];
+
+    data.splice(0..0, ValidateLeafHash::DISCRIMINATOR.iter().cloned());
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data,
+    }
+}
+
+pub fn trace_instruction(program_id: Pubkey, mpt_account: Pubkey, key: Vec<u8>) -> Instruction {
+    let instruction_data = TraceProof { key };
+    let mut data = borsh::to_vec(&instruction_data).unwrap();
+
+    let account_metas = vec![AccountMeta::new(mpt_account, false)];
+
+    data.splice(0..0, TraceProof::DISCRIMINATOR.iter().cloned());
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data,
+    }
+}
+
+/// Checks off-chain that the receipts hash to the given root, then uploads
+/// them to the values account for that root and has the program check the
+/// same.
+fn validate_receipts(
+    client: &rpc_client::RpcClient,
+    signer: &Keypair,
+    program_id: Pubkey,
+    params: &ReceiptsParams,
+) -> Result<()> {
+    let receipts = params.receipts();
+    let root = EthTrieBuilder::ordered_root(&receipts);
+    if root.to_fixed_bytes() != params.root() {
+        return Err(eyre!("receipts hash to {:?}", root));
+    }
+
+    let mut len: usize = 4;
+    for v in &receipts {
+        len += 4 + v.len();
+    }
+    let seeds: &[&[u8]] = &[VALUES_SEED, root.as_bytes()];
+    let (values_account, _) = Pubkey::find_program_address(seeds, &program_id);
+
+    let init_ix = init_values_instruction(
+        program_id,
+        signer.pubkey(),
+        values_account,
+        params.root(),
+        len as u32,
+    );
+    send_transaction(client, signer, &[init_ix], "init_values_instruction");
+
+    upload_proof(
+        client,
+        signer,
+        program_id,
+        values_account,
+        &receipts,
+        append_values_instruction,
+    );
+
+    let ix = receipts_root_instruction(program_id, values_account);
+    send_transaction(client, signer, &[ix], "validate_receipts_root");
+    Ok(())
+}
+
+pub fn init_values_instruction(
+    program_id: Pubkey,
+    payer: Pubkey,
+    values_account: Pubkey,
+    hash_root: [u8; 32],
+    values_size: u32,
+) -> Instruction {
+    let instruction_data = InitValues {
+        params: InitProofParams {
+            hash_root,
+            proof_size: values_size,
+        },
+    };
+    let mut data = borsh::to_vec(&instruction_data).unwrap();
+    let account_metas = vec![
+        AccountMeta::new(payer, true),
+        AccountMeta::new(values_account, false),
+        AccountMeta::new_readonly(system_program::ID, false),
+    ];
+
+    data.splice(0..0, InitValues::DISCRIMINATOR.iter().cloned());
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data,
+    }
+}
+
+pub fn append_values_instruction(
+    program_id: Pubkey,
+    values_account: Pubkey,
+    data: Vec<Vec<u8>>,
+    is_merge: bool,
+) -> Instruction {
+    let instruction_data = AppendValues { data, is_merge };
+    let mut data = borsh::to_vec(&instruction_data).unwrap();
+    let account_metas = vec![AccountMeta::new(values_account, false)];
+
+    data.splice(0..0, AppendValues::DISCRIMINATOR.iter().cloned());
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data,
+    }
+}
+
+pub fn receipts_root_instruction(program_id: Pubkey, values_account: Pubkey) -> Instruction {
+    let instruction_data = ValidateReceiptsRoot {};
+    let mut data = borsh::to_vec(&instruction_data).unwrap();
+
+    let account_metas = vec![AccountMeta::new(values_account, false)];
+
+    data.splice(0..0, ValidateReceiptsRoot::DISCRIMINATOR.iter().cloned());
+
+    Instruction {
+        program_id,
+        accounts: account_metas,
+        data,
+    }
+}
+
+/// Uploads `items` with `append`, at most `TX_MAX_SIZE` bytes per
+/// transaction. An item that does not fit is split, and the next transaction
+/// merges the rest into it.
+fn upload_proof(
+    client: &rpc_client::RpcClient,
+    signer: &Keypair,
+    program_id: Pubkey,
+    mpt_account: Pubkey,
+    items: &[Vec<u8>],
+    append: fn(Pubkey, Pubkey, Vec<Vec<u8>>, bool) -> Instruction,
+) {
+    let mut data = Vec::new();
+    let mut remaining_length = TX_MAX_SIZE;
+    let mut is_merge = false;
+    for item in items {
+        let mut rest = item.as_slice();
+        while !rest.is_empty() {
+            if remaining_length == 0 {
+                let ix = append(program_id, mpt_account, data.clone(), is_merge);
+                send_transaction(client, signer, &[ix], "append_proof ");
+                data.clear();
+                remaining_length = TX_MAX_SIZE;
+                is_merge = rest.len() < item.len();
+            }
+            let take = rest.len().min(remaining_length);
+            data.push(rest[..take].to_vec());
+            rest = &rest[take..];
+            remaining_length -= take;
+        }
+    }
+
+    // 最后一个循环可能还有数据
+    if !data.is_empty() {
+        let ix = append(program_id, mpt_account, data, is_merge);
+        send_transaction(client, signer, &[ix], "append_proof ");
+    }
+}
+
 fn to_hex_string(bytes: &[u8]) -> String {
     bytes.iter().map(|b: &u8| format!("{:02x}", b)).collect()
 }
@@ -215,7 +439,7 @@ fn to_hex_string(bytes: &[u8]) -> String {
 fn send_transaction(
     client: &rpc_client::RpcClient,
     signer: &Keypair,
-    ixs: &Vec<Instruction>,
+    ixs: &[Instruction],
     msg: &str,
 ) {
     let latest_blockhash = client.get_latest_blockhash().unwrap();
@@ -230,3 +454,42 @@ fn send_transaction(
 
     println!("{} tx: {}", msg, sig);
 }
+
+fn print_trace(client: &rpc_client::RpcClient, signer: &Keypair, ixs: &[Instruction]) {
+    let latest_blockhash = client.get_latest_blockhash().unwrap();
+    let result = client
+        .simulate_transaction(&transaction::Transaction::new_signed_with_payer(
+            ixs,
+            Some(&signer.pubkey()),
+            &[&signer],
+            latest_blockhash,
+        ))
+        .unwrap();
+
+    let Some(return_data) = result.value.return_data else {
+        println!("trace: no return data, logs: {:?}", result.value.logs);
+        return;
+    };
+    let bytes = STANDARD.decode(&return_data.data.0).unwrap();
+    let trace = ProofTraceInfo::deserialize(&mut bytes.as_slice()).unwrap();
+
+    println!("trace: present {} error {:?}", trace.present, trace.error);
+    if trace.skipped > 0 {
+        println!("... {} steps skipped", trace.skipped);
+    }
+    for (i, step) in trace.steps.iter().enumerate() {
+        let i = i + usize::from(trace.skipped);
+        let kind = match step.kind {
+            0 => "empty",
+            1 => "leaf",
+            2 => "extension",
+            3 => "branch",
+            _ => "unknown",
+        };
+        let hash = match &step.hash {
+            Some(hash) => to_hex_string(hash),
+            None => "embedded".to_owned(),
+        };
+        println!("{:>3} {:<9} nibbles {:>3} {}", i, kind, step.nibbles, hash);
+    }
+}
@@ -0,0 +1,51 @@
+use primitive_types_solana::{H256, U256};
+use rlp::{Decodable, DecoderError, Rlp};
+
+/// Account leaf of the Ethereum state trie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthAccount {
+    pub nonce: u64,
+    pub balance: U256,
+    pub storage_root: H256,
+    pub code_hash: H256,
+}
+
+impl Decodable for EthAccount {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let balance = rlp.at(1)?.data()?;
+        if balance.len() > 32 {
+            return Err(DecoderError::RlpIsTooBig);
+        }
+
+        Ok(EthAccount {
+            nonce: rlp.val_at(0)?,
+            balance: U256::from_big_endian(balance),
+            storage_root: decode_hash(&rlp.at(2)?)?,
+            code_hash: decode_hash(&rlp.at(3)?)?,
+        })
+    }
+}
+
+fn decode_hash(rlp: &Rlp) -> Result<H256, DecoderError> {
+    let data = rlp.data()?;
+    if data.len() != 32 {
+        return Err(DecoderError::RlpInvalidLength);
+    }
+    Ok(H256::from_slice(data))
+}
+
+/// Decodes a storage trie leaf, an RLP string holding the slot value with
+/// leading zeros stripped, into a 32-byte word.
+pub fn decode_storage_value(data: &[u8]) -> Result<H256, DecoderError> {
+    let value = Rlp::new(data).data()?;
+    if value.len() > 32 {
+        return Err(DecoderError::RlpIsTooBig);
+    }
+    let mut word = [0u8; 32];
+    word[32 - value.len()..].copy_from_slice(value);
+    Ok(H256(word))
+}
@@ -0,0 +1,149 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use primitive_types_solana::H256;
+use rlp::RlpStream;
+
+use crate::hasher::{Keccak, TrieHasher};
+use crate::nibbles::Nibbles;
+use crate::trie::index_key;
+
+const HASHED_LENGTH: usize = 32;
+
+/// Builds a Patricia trie from key/value pairs and computes its root. Nodes
+/// are encoded bottom-up from the sorted pairs, so the whole trie is never
+/// held in memory; this keeps it usable on-chain for small tries.
+#[derive(Debug)]
+pub struct PatriciaTrieBuilder<H: TrieHasher> {
+    /// Keys as nibbles without a terminator, sorted and unique.
+    entries: Vec<(Nibbles, Vec<u8>)>,
+    hasher: PhantomData<H>,
+}
+
+pub type EthTrieBuilder = PatriciaTrieBuilder<Keccak>;
+
+impl<H: TrieHasher> Default for PatriciaTrieBuilder<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: TrieHasher> PatriciaTrieBuilder<H> {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            hasher: PhantomData,
+        }
+    }
+
+    /// Inserts `value` at `key`, replacing any previous value. A Patricia
+    /// trie does not store empty values, so an empty `value` removes `key`.
+    pub fn insert(&mut self, key: &[u8], value: Vec<u8>) {
+        let key = Nibbles::from_raw(key, false);
+        match self.entries.binary_search_by(|(k, _)| k.cmp(&key)) {
+            Ok(i) if value.is_empty() => {
+                self.entries.remove(i);
+            }
+            Ok(i) => self.entries[i].1 = value,
+            Err(_) if value.is_empty() => {}
+            Err(i) => self.entries.insert(i, (key, value)),
+        }
+    }
+
+    /// Inserts `value` under `rlp(index)`, as in the receipt and transaction
+    /// tries.
+    pub fn insert_index(&mut self, index: u64, value: Vec<u8>) {
+        self.insert(&index_key(index), value);
+    }
+
+    /// Root of a receipt or transaction trie holding `values` in block order.
+    pub fn ordered_root<V: AsRef<[u8]>>(values: &[V]) -> H256 {
+        let mut builder = Self::new();
+        for (index, value) in values.iter().enumerate() {
+            builder.insert_index(index as u64, value.as_ref().to_vec());
+        }
+        builder.root()
+    }
+
+    pub fn root(&self) -> H256 {
+        if self.entries.is_empty() {
+            return H::hash(&rlp::NULL_RLP);
+        }
+        H::hash(&Self::encode_node(&self.entries, 0))
+    }
+
+    /// Reference to the node holding `entries`, whose keys all share their
+    /// first `depth` nibbles, as its parent stores it. Empty if there are no
+    /// entries.
+    pub(crate) fn reference(entries: &[(Nibbles, Vec<u8>)], depth: usize) -> Vec<u8> {
+        if entries.is_empty() {
+            return rlp::NULL_RLP.to_vec();
+        }
+        let mut stream = RlpStream::new();
+        Self::append_child(&mut stream, entries, depth);
+        stream.out().to_vec()
+    }
+
+    /// RLP encoding of the node holding `entries`, whose keys all share their
+    /// first `depth` nibbles.
+    fn encode_node(entries: &[(Nibbles, Vec<u8>)], depth: usize) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+
+        if let [(key, value)] = entries {
+            let mut path = key.offset(depth).as_slice().to_vec();
+            path.push(16);
+            stream.begin_list(2);
+            stream.append(&Nibbles::from_hex(&path).encode_compact());
+            stream.append(value);
+            return stream.out().to_vec();
+        }
+
+        // Entries are sorted, so the first and last bound the shared prefix.
+        let first = &entries[0].0;
+        let last = &entries[entries.len() - 1].0;
+        let shared = first.offset(depth).common_prefix(&last.offset(depth));
+        if shared > 0 {
+            stream.begin_list(2);
+            stream.append(&first.slice(depth, depth + shared).encode_compact());
+            Self::append_child(&mut stream, entries, depth + shared);
+            return stream.out().to_vec();
+        }
+
+        // A key ending here sorts first and becomes the branch value.
+        let (value, mut rest) = if first.len() == depth {
+            (Some(&entries[0].1), &entries[1..])
+        } else {
+            (None, entries)
+        };
+
+        stream.begin_list(17);
+        for nibble in 0..16 {
+            let count = rest
+                .iter()
+                .take_while(|(key, _)| key.at(depth) == nibble)
+                .count();
+            if count == 0 {
+                stream.append_empty_data();
+            } else {
+                Self::append_child(&mut stream, &rest[..count], depth + 1);
+                rest = &rest[count..];
+            }
+        }
+        match value {
+            Some(value) => stream.append(value),
+            None => stream.append_empty_data(),
+        };
+        stream.out().to_vec()
+    }
+
+    /// Appends a reference to a child node: embedded if its encoding is under
+    /// 32 bytes, otherwise its hash.
+    fn append_child(stream: &mut RlpStream, entries: &[(Nibbles, Vec<u8>)], depth: usize) {
+        let encoded = Self::encode_node(entries, depth);
+        if encoded.len() < HASHED_LENGTH {
+            stream.append_raw(&encoded, 1);
+        } else {
+            stream.append(&H::hash(&encoded).as_bytes());
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use alloc::borrow::ToOwned;
+use alloc::format;
+use core::fmt;
+
+use primitive_types_solana::H256;
+
+use rlp::DecoderError;
+
+use crate::node::NodeKind;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TrieError {
+    Decoder(DecoderError),
+    InvalidData,
+    InvalidProof,
+    /// The `node`-th node on the path (counting from the root, embedded nodes
+    /// included) is not valid RLP.
+    BadRlp {
+        node: usize,
+        error: DecoderError,
+    },
+    /// No proof node hashes to the reference found after `depth` nibbles.
+    HashMismatch {
+        depth: usize,
+        expected: H256,
+    },
+    /// The key is not in the trie; its path left the trie at nibble `nibble`.
+    PathDiverged {
+        nibble: usize,
+    },
+    /// A node of the wrong kind, or not a node at all (`kind` is `None`), was
+    /// found after `depth` nibbles.
+    UnexpectedNode {
+        depth: usize,
+        kind: Option<NodeKind>,
+    },
+    DepthExceeded,
+    UnusedNode(H256),
+    DuplicateNode(H256),
+    OutOfOrderNode(H256),
+    NonCanonicalInlineNode,
+    /// Range proof entries are unsorted, duplicated, empty or outside the
+    /// range.
+    InvalidRange,
+    /// The range proof entries do not match the subtrie reached after `depth`
+    /// nibbles.
+    RangeMismatch {
+        depth: usize,
+    },
+    /// A compact proof is truncated, malformed or of an unknown version.
+    BadProofEncoding,
+    /// The leaf found after `depth` nibbles does not hash to the committed
+    /// leaf hash.
+    LeafHashMismatch {
+        depth: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TrieError {}
+
+impl fmt::Display for TrieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let printable = match *self {
+            // TrieError::DB(ref err) => format!("trie error: {:?}", err),
+            TrieError::Decoder(ref err) => format!("trie error: {:?}", err),
+            TrieError::InvalidData => "trie error: invalid data".to_owned(),
+            TrieError::InvalidProof => "trie error: invalid proof".to_owned(),
+            TrieError::BadRlp { node, ref error } => {
+                format!("trie error: bad rlp at node {}: {:?}", node, error)
+            }
+            TrieError::HashMismatch { depth, expected } => {
+                format!("trie error: no node for {:?} at depth {}", expected, depth)
+            }
+            TrieError::PathDiverged { nibble } => {
+                format!("trie error: path diverged at nibble {}", nibble)
+            }
+            TrieError::UnexpectedNode { depth, kind } => {
+                format!("trie error: unexpected node {:?} at depth {}", kind, depth)
+            }
+            TrieError::DepthExceeded => "trie error: max depth exceeded".to_owned(),
+            TrieError::UnusedNode(hash) => format!("trie error: unused node {:?}", hash),
+            TrieError::DuplicateNode(hash) => format!("trie error: duplicate node {:?}", hash),
+            TrieError::OutOfOrderNode(hash) => format!("trie error: out of order node {:?}", hash),
+            TrieError::NonCanonicalInlineNode => "trie error: non-canonical inline node".to_owned(),
+            TrieError::InvalidRange => "trie error: invalid range entries".to_owned(),
+            TrieError::RangeMismatch { depth } => {
+                format!("trie error: range entries mismatch at depth {}", depth)
+            }
+            TrieError::BadProofEncoding => "trie error: bad compact proof encoding".to_owned(),
+            TrieError::LeafHashMismatch { depth } => {
+                format!("trie error: leaf hash mismatch at depth {}", depth)
+            }
+        };
+        write!(f, "{}", printable)
+    }
+}
+
+impl From<DecoderError> for TrieError {
+    fn from(error: DecoderError) -> Self {
+        TrieError::Decoder(error)
+    }
+}
@@ -0,0 +1,55 @@
+use primitive_types_solana::H256;
+#[cfg(feature = "solana")]
+use solana_program::{blake3, hash, keccak};
+
+/// Node hash function of a Patricia trie. With the `solana` feature each
+/// implementation is backed by a Solana syscall.
+pub trait TrieHasher {
+    fn hash(data: &[u8]) -> H256;
+}
+
+/// Ethereum's keccak-256.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Keccak;
+
+#[cfg(feature = "solana")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sha256;
+
+#[cfg(feature = "solana")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Blake3;
+
+#[cfg(feature = "solana")]
+impl TrieHasher for Keccak {
+    fn hash(data: &[u8]) -> H256 {
+        H256(keccak::hash(data).to_bytes())
+    }
+}
+
+#[cfg(not(feature = "solana"))]
+impl TrieHasher for Keccak {
+    fn hash(data: &[u8]) -> H256 {
+        use tiny_keccak::{Hasher, Keccak as Keccak256};
+
+        let mut output = [0u8; 32];
+        let mut keccak = Keccak256::v256();
+        keccak.update(data);
+        keccak.finalize(&mut output);
+        H256(output)
+    }
+}
+
+#[cfg(feature = "solana")]
+impl TrieHasher for Sha256 {
+    fn hash(data: &[u8]) -> H256 {
+        H256(hash::hash(data).to_bytes())
+    }
+}
+
+#[cfg(feature = "solana")]
+impl TrieHasher for Blake3 {
+    fn hash(data: &[u8]) -> H256 {
+        H256(blake3::hash(data).to_bytes())
+    }
+}
@@ -0,0 +1,23 @@
+//! Ethereum Merkle Patricia trie proof verification, shared by the on-chain
+//! program and off-chain tools.
+//!
+//! The crate is `no_std` and needs only `alloc`. With the `solana` feature
+//! node hashes go through the Solana hashing syscalls, which also enables the
+//! SHA-256 and BLAKE3 hashers; without it keccak is computed in pure Rust.
+//! The `std` feature implements `std::error::Error` for `TrieError`.
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod account;
+pub mod builder;
+pub mod errors;
+pub mod hasher;
+pub mod nibbles;
+pub mod node;
+pub mod proof;
+pub mod trie;
+
+pub use primitive_types_solana::H256;
@@ -1,6 +1,10 @@
-use std::cmp::min;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::min;
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+use crate::errors::TrieError;
+
+#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]
 pub struct Nibbles {
     hex_data: Vec<u8>,
 }
@@ -24,12 +28,15 @@ impl Nibbles {
         Nibbles { hex_data }
     }
 
-    pub fn from_compact(compact: &[u8]) -> Self {
+    /// Decodes a hex-prefix encoded path. Fails on an empty path, an unknown
+    /// flag nibble or a non-zero padding nibble.
+    pub fn from_compact(compact: &[u8]) -> Result<Self, TrieError> {
         let mut hex = vec![];
-        let flag = compact[0];
+        let flag = *compact.first().ok_or(TrieError::InvalidData)?;
 
         let mut is_leaf = false;
         match flag >> 4 {
+            0x0 | 0x2 if flag % 16 != 0 => return Err(TrieError::InvalidData),
             0x0 => {}
             0x1 => hex.push(flag % 16),
             0x2 => is_leaf = true,
@@ -37,7 +44,7 @@ impl Nibbles {
                 is_leaf = true;
                 hex.push(flag % 16);
             }
-            _ => panic!("invalid data"),
+            _ => return Err(TrieError::InvalidData),
         }
 
         for item in &compact[1..] {
@@ -48,11 +55,38 @@ impl Nibbles {
             hex.push(16);
         }
 
-        Nibbles { hex_data: hex }
+        Ok(Nibbles { hex_data: hex })
+    }
+
+    /// Hex-prefix encoding of the path, the inverse of `from_compact`. A
+    /// trailing terminator nibble (16) marks a leaf path.
+    pub fn encode_compact(&self) -> Vec<u8> {
+        let is_leaf = self.is_leaf();
+        let mut hex = if is_leaf {
+            &self.hex_data[..self.hex_data.len() - 1]
+        } else {
+            &self.hex_data[..]
+        };
+
+        let mut flag = if is_leaf { 0x20 } else { 0x00 };
+        if hex.len() % 2 == 1 {
+            flag |= 0x10 | hex[0];
+            hex = &hex[1..];
+        }
+
+        let mut compact = vec![flag];
+        for pair in hex.chunks(2) {
+            compact.push(pair[0] << 4 | pair[1]);
+        }
+        compact
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.hex_data
     }
 
     pub fn is_leaf(&self) -> bool {
-        self.hex_data[self.hex_data.len() - 1] == 16
+        self.hex_data.last() == Some(&16)
     }
 
     pub fn len(&self) -> usize {
@@ -0,0 +1,143 @@
+use primitive_types_solana::H256;
+use rlp::{DecoderError, Prototype, Rlp};
+
+use crate::errors::TrieError;
+use crate::nibbles::Nibbles;
+
+const HASHED_LENGTH: usize = 32;
+
+/// A trie node decoded in place over its RLP encoding. Values and children
+/// borrow from the proof data; branch children are only decoded when visited.
+#[derive(Debug, Clone)]
+pub enum Node<'a> {
+    Empty,
+    Leaf(LeafNode<'a>),
+    Extension(ExtensionNode<'a>),
+    Branch(BranchNode<'a>),
+    Hash(H256),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Empty,
+    Leaf,
+    Extension,
+    Branch,
+    Hash,
+}
+
+impl<'a> Node<'a> {
+    pub fn kind(&self) -> NodeKind {
+        match self {
+            Node::Empty => NodeKind::Empty,
+            Node::Leaf(_) => NodeKind::Leaf,
+            Node::Extension(_) => NodeKind::Extension,
+            Node::Branch(_) => NodeKind::Branch,
+            Node::Hash(_) => NodeKind::Hash,
+        }
+    }
+
+    pub fn decode(data: &'a [u8]) -> Result<Self, TrieError> {
+        let r = Rlp::new(data);
+        check_span(&r)?;
+
+        match r.prototype()? {
+            Prototype::Data(0) => Ok(Node::Empty),
+            Prototype::List(2) => {
+                let key = Nibbles::from_compact(r.at(0)?.data()?)?;
+
+                if key.is_leaf() {
+                    Ok(Node::Leaf(LeafNode {
+                        key,
+                        value: r.at(1)?.data()?,
+                    }))
+                } else if key.is_empty() {
+                    // An extension always shares at least one nibble.
+                    Err(TrieError::InvalidData)
+                } else {
+                    Ok(Node::Extension(ExtensionNode {
+                        prefix: key,
+                        node: r.at(1)?.as_raw(),
+                    }))
+                }
+            }
+            Prototype::List(17) => Ok(Node::Branch(BranchNode { rlp: r })),
+            _ => {
+                if r.is_data() && r.size() == HASHED_LENGTH {
+                    Ok(Node::Hash(H256::from_slice(r.data()?)))
+                } else {
+                    Err(TrieError::InvalidData)
+                }
+            }
+        }
+    }
+}
+
+/// Checks that `r` is exactly one item and, if it is a list, that its items
+/// fill it. `Rlp` reads past neither end but stops counting items at the
+/// first malformed one, which would pass a corrupt node off as a shorter
+/// list.
+fn check_span(r: &Rlp) -> Result<(), DecoderError> {
+    let len = r.as_raw().len();
+    if r.payload_info()?.total() != len {
+        return Err(DecoderError::RlpInconsistentLengthAndData);
+    }
+    if r.is_list() {
+        let end = match r.item_count()? {
+            0 => r.payload_info()?.header_len,
+            count => {
+                let (last, offset) = r.at_with_offset(count - 1)?;
+                offset + last.as_raw().len()
+            }
+        };
+        if end != len {
+            return Err(DecoderError::RlpInconsistentLengthAndData);
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct LeafNode<'a> {
+    pub key: Nibbles,
+    pub value: &'a [u8],
+}
+
+#[derive(Debug, Clone)]
+pub struct ExtensionNode<'a> {
+    pub prefix: Nibbles,
+    /// RLP encoding of the child, either a hash or an embedded node.
+    pub node: &'a [u8],
+}
+
+impl<'a> ExtensionNode<'a> {
+    pub fn child(&self) -> Result<Node<'a>, TrieError> {
+        Node::decode(self.node)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BranchNode<'a> {
+    rlp: Rlp<'a>,
+}
+
+impl<'a> BranchNode<'a> {
+    pub fn child(&self, index: usize) -> Result<Node<'a>, TrieError> {
+        Node::decode(self.child_raw(index)?)
+    }
+
+    /// RLP encoding of child `index`, either a hash or an embedded node.
+    pub fn child_raw(&self, index: usize) -> Result<&'a [u8], TrieError> {
+        Ok(self.rlp.at(index)?.as_raw())
+    }
+
+    /// The value stored at this branch, held in its last element.
+    pub fn value(&self) -> Result<Option<&'a [u8]>, TrieError> {
+        let value_rlp = self.rlp.at(16)?;
+        if value_rlp.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(value_rlp.data()?))
+        }
+    }
+}
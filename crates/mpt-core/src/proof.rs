@@ -0,0 +1,132 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use primitive_types_solana::H256;
+
+use crate::errors::TrieError;
+use crate::trie::TrieResult;
+
+/// Version byte opening a compact proof. Proof nodes are RLP lists, which
+/// start at `0xc0`, so it also tells a compact proof apart from a raw node.
+pub const COMPACT_PROOF_VERSION: u8 = 1;
+
+/// A proof in the compact binary format. Integers are little-endian:
+///
+/// | field        | size               |
+/// |--------------|--------------------|
+/// | version      | 1                  |
+/// | root         | 32                 |
+/// | key length   | 2                  |
+/// | key          | key length         |
+/// | node count   | 2                  |
+/// | node ends    | 4 × node count     |
+/// | nodes        | rest               |
+///
+/// `node ends` holds each node's end offset within `nodes`. Identical nodes,
+/// as found in multiproofs, are stored once. `key` is the key the proof is
+/// for, or empty for a proof of several keys; verifiers reject a proof whose
+/// key is not the one they check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompactProof<'a> {
+    pub root: H256,
+    pub key: &'a [u8],
+    /// Nodes borrowed from the encoded proof, in upload order.
+    pub nodes: Vec<&'a [u8]>,
+}
+
+impl<'a> CompactProof<'a> {
+    pub fn is_compact(data: &[u8]) -> bool {
+        data.first() == Some(&COMPACT_PROOF_VERSION)
+    }
+
+    /// Encodes a proof, keeping the first copy of each repeated node. Fails
+    /// with `TrieError::BadProofEncoding` if the key or node count does not
+    /// fit in 16 bits.
+    pub fn encode<P: AsRef<[u8]>>(root: H256, key: &[u8], nodes: &[P]) -> TrieResult<Vec<u8>> {
+        let nodes: Vec<&[u8]> = nodes.iter().map(AsRef::as_ref).collect();
+        // A stable sort puts the first copy of each node ahead of the others.
+        let mut order: Vec<usize> = (0..nodes.len()).collect();
+        order.sort_by_key(|&i| nodes[i]);
+        let mut repeated = vec![false; nodes.len()];
+        for pair in order.windows(2) {
+            repeated[pair[1]] = nodes[pair[0]] == nodes[pair[1]];
+        }
+        let unique: Vec<&[u8]> = nodes
+            .iter()
+            .zip(repeated)
+            .filter_map(|(node, repeated)| (!repeated).then_some(*node))
+            .collect();
+        let key_len = u16::try_from(key.len()).map_err(|_| TrieError::BadProofEncoding)?;
+        let count = u16::try_from(unique.len()).map_err(|_| TrieError::BadProofEncoding)?;
+
+        let body: usize = unique.iter().map(|node| node.len()).sum();
+        let mut out = Vec::with_capacity(37 + key.len() + 4 * unique.len() + body);
+        out.push(COMPACT_PROOF_VERSION);
+        out.extend_from_slice(root.as_bytes());
+        out.extend_from_slice(&key_len.to_le_bytes());
+        out.extend_from_slice(key);
+        out.extend_from_slice(&count.to_le_bytes());
+        let mut end = 0u32;
+        for node in &unique {
+            end = u32::try_from(node.len())
+                .ok()
+                .and_then(|len| end.checked_add(len))
+                .ok_or(TrieError::BadProofEncoding)?;
+            out.extend_from_slice(&end.to_le_bytes());
+        }
+        for node in unique {
+            out.extend_from_slice(node);
+        }
+        Ok(out)
+    }
+
+    /// Decodes a compact proof without copying. Fails with
+    /// `TrieError::BadProofEncoding` on an unknown version, truncated input,
+    /// decreasing node ends or trailing bytes.
+    pub fn decode(data: &'a [u8]) -> TrieResult<Self> {
+        let mut reader = Reader(data);
+        if reader.take(1)? != [COMPACT_PROOF_VERSION] {
+            return Err(TrieError::BadProofEncoding);
+        }
+        let root = H256::from_slice(reader.take(32)?);
+        let key_len = reader.u16()?;
+        let key = reader.take(key_len as usize)?;
+        let count = reader.u16()? as usize;
+
+        let ends = reader.take(4 * count)?;
+        let body = reader.0;
+        let mut nodes = Vec::with_capacity(count);
+        let mut start = 0;
+        for end in ends.chunks_exact(4) {
+            let end = u32::from_le_bytes([end[0], end[1], end[2], end[3]]) as usize;
+            if end < start || end > body.len() {
+                return Err(TrieError::BadProofEncoding);
+            }
+            nodes.push(&body[start..end]);
+            start = end;
+        }
+        if start != body.len() {
+            return Err(TrieError::BadProofEncoding);
+        }
+
+        Ok(CompactProof { root, key, nodes })
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> TrieResult<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(TrieError::BadProofEncoding);
+        }
+        let (head, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> TrieResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+}
@@ -0,0 +1,1047 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use primitive_types_solana::H256;
+use rlp::{Rlp, RlpStream};
+
+use crate::account::{decode_storage_value, EthAccount};
+use crate::builder::PatriciaTrieBuilder;
+use crate::errors::TrieError;
+use crate::hasher::{Keccak, TrieHasher};
+use crate::nibbles::Nibbles;
+use crate::node::{BranchNode, Node, NodeKind};
+
+pub type TrieResult<T> = Result<T, TrieError>;
+const HASHED_LENGTH: usize = 32;
+/// Default bound on the number of nodes decoded for one key, counting the
+/// root and embedded nodes. Every node above the last consumes at least one
+/// nibble, so an honest path for a 32-byte key decodes at most 65 nodes.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Trie path for `key` in an Ethereum secure trie, `keccak(key)`.
+pub fn secure_key(key: &[u8]) -> [u8; 32] {
+    Keccak::hash(key).to_fixed_bytes()
+}
+
+/// Trie key for the receipt or transaction at `index` in a block, `rlp(index)`.
+pub fn index_key(index: u64) -> Vec<u8> {
+    rlp::encode(&index).to_vec()
+}
+
+/// Splits `[first, last]` into the index ranges whose `rlp(index)` keys are
+/// contiguous in trie order. Keys sort by length before value, so index `0`
+/// (`0x80`) sorts after `1..=127` (`0x01..=0x7f`), and each of `128..=255`
+/// (`0x81xx`), `256..=65535` (`0x82xxxx`), ... forms a span of its own.
+/// Spans are returned in index order.
+pub fn index_spans(first: u64, last: u64) -> Vec<(u64, u64)> {
+    let mut classes = vec![(0, 0), (1, 127), (128, 255)];
+    for len in 2..8 {
+        classes.push((1 << (8 * (len - 1)), (1 << (8 * len)) - 1));
+    }
+    classes.push((1 << 56, u64::MAX));
+
+    classes
+        .into_iter()
+        .filter(|&(lo, hi)| lo <= last && first <= hi)
+        .map(|(lo, hi)| (lo.max(first), hi.min(last)))
+        .collect()
+}
+
+/// Result of walking a proof for a single key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProofOutcome<'a> {
+    /// The key is in the trie with this value, borrowed from the proof.
+    Present(&'a [u8]),
+    /// The key is provably not in the trie. `divergence_node` is the hash of the
+    /// last hashed node on the path and `depth` the number of nibbles consumed
+    /// when the path left the trie.
+    Absent { divergence_node: H256, depth: usize },
+}
+
+impl<'a> ProofOutcome<'a> {
+    pub fn into_value(self) -> Option<&'a [u8]> {
+        match self {
+            ProofOutcome::Present(value) => Some(value),
+            ProofOutcome::Absent { .. } => None,
+        }
+    }
+
+    /// The proven value, or `TrieError::PathDiverged` if the key is absent.
+    pub fn require_present(self) -> TrieResult<&'a [u8]> {
+        match self {
+            ProofOutcome::Present(value) => Ok(value),
+            ProofOutcome::Absent { depth, .. } => Err(TrieError::PathDiverged { nibble: depth }),
+        }
+    }
+}
+
+/// Proof verifier over borrowed proof nodes. Nothing is copied out of the
+/// proof: nodes are decoded in place as the key's path reaches them. `H` is
+/// the node hash function.
+#[derive(Debug)]
+pub struct PatriciaTrie<'a, H: TrieHasher> {
+    root_hash: H256,
+    /// Proof nodes keyed by hash, sorted and deduplicated so lookups are a
+    /// binary search.
+    nodes: Vec<(H256, &'a [u8])>,
+    max_depth: usize,
+    strict: bool,
+    hasher: PhantomData<H>,
+}
+
+/// Ethereum's keccak-hashed Merkle Patricia trie.
+pub type EthTrie<'a> = PatriciaTrie<'a, Keccak>;
+
+/// One node visited while walking a key's path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStep {
+    /// Hash the node was looked up by; `None` for nodes embedded in their parent.
+    pub hash: Option<H256>,
+    pub kind: NodeKind,
+    /// Nibbles of the key consumed before reaching this node.
+    pub nibbles: usize,
+}
+
+/// Children and value of a branch, as held by `Written::Branch`.
+type BranchParts = (Vec<Vec<u8>>, Option<Vec<u8>>);
+
+/// A node rebuilt by `update`. Paths are nibbles without the leaf
+/// terminator; children are RLP references, either a hash or an embedded node.
+#[derive(Debug, Clone)]
+enum Written {
+    Empty,
+    Leaf(Vec<u8>, Vec<u8>),
+    Extension(Vec<u8>, Vec<u8>),
+    Branch(Vec<Vec<u8>>, Option<Vec<u8>>),
+}
+
+impl Written {
+    fn from_node(node: &Node) -> TrieResult<Self> {
+        Ok(match node {
+            Node::Empty => Written::Empty,
+            Node::Leaf(leaf) => {
+                let key = leaf.key.as_slice();
+                Written::Leaf(key[..key.len() - 1].to_vec(), leaf.value.to_vec())
+            }
+            Node::Extension(extension) => Written::Extension(
+                extension.prefix.as_slice().to_vec(),
+                extension.node.to_vec(),
+            ),
+            Node::Branch(branch) => {
+                let (children, value) = Self::branch_parts(branch)?;
+                Written::Branch(children, value)
+            }
+            Node::Hash(_) => return Err(TrieError::InvalidData),
+        })
+    }
+
+    fn branch_parts(branch: &BranchNode) -> TrieResult<BranchParts> {
+        let children = (0..16)
+            .map(|i| branch.child_raw(i).map(<[u8]>::to_vec))
+            .collect::<TrieResult<_>>()?;
+        Ok((children, branch.value()?.map(<[u8]>::to_vec)))
+    }
+
+    /// The node reached through `prefix`: paths are joined so that no
+    /// extension points at a leaf or another extension.
+    fn with_prefix<H: TrieHasher>(prefix: &[u8], node: Written) -> Written {
+        match node {
+            Written::Leaf(key, value) => Written::Leaf([prefix, &key].concat(), value),
+            Written::Extension(key, child) => Written::Extension([prefix, &key].concat(), child),
+            Written::Branch(..) if !prefix.is_empty() => {
+                Written::Extension(prefix.to_vec(), node.reference::<H>())
+            }
+            node => node,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        match self {
+            Written::Empty => return rlp::NULL_RLP.to_vec(),
+            Written::Leaf(key, value) => {
+                let path = Nibbles::from_hex(&[key.as_slice(), &[16]].concat());
+                stream.begin_list(2);
+                stream.append(&path.encode_compact());
+                stream.append(value);
+            }
+            Written::Extension(key, child) => {
+                stream.begin_list(2);
+                stream.append(&Nibbles::from_hex(key).encode_compact());
+                stream.append_raw(child, 1);
+            }
+            Written::Branch(children, value) => {
+                stream.begin_list(17);
+                for child in children {
+                    stream.append_raw(child, 1);
+                }
+                match value {
+                    Some(value) => stream.append(value),
+                    None => stream.append_empty_data(),
+                };
+            }
+        }
+        stream.out().to_vec()
+    }
+
+    /// Reference to this node from its parent: embedded if its encoding is
+    /// under 32 bytes, otherwise its hash.
+    fn reference<H: TrieHasher>(&self) -> Vec<u8> {
+        let encoded = self.encode();
+        if encoded.len() < HASHED_LENGTH {
+            encoded
+        } else {
+            rlp::encode(&H::hash(&encoded).as_bytes()).to_vec()
+        }
+    }
+}
+
+/// A node above the write on the key's path, kept by `write` until the
+/// nodes below it are rebuilt.
+enum Parent {
+    /// An extension over these nibbles.
+    Extension(Vec<u8>),
+    /// A branch, the `index`-th node on the path and `depth` nibbles deep,
+    /// followed into child `nibble`.
+    Branch {
+        children: Vec<Vec<u8>>,
+        value: Option<Vec<u8>>,
+        nibble: usize,
+        depth: usize,
+        index: usize,
+    },
+}
+
+/// Where the keys under a path fall relative to a key range.
+enum Span {
+    /// All keys under the path are outside the range.
+    Outside,
+    /// All keys under the path are inside the range.
+    Inside,
+    /// The path leads to one of the range bounds.
+    Edge,
+}
+
+impl Span {
+    fn of(path: &[u8], first: &[u8], last: &[u8]) -> Span {
+        if first.starts_with(path) || last.starts_with(path) {
+            Span::Edge
+        } else if path < first || path > last {
+            Span::Outside
+        } else {
+            Span::Inside
+        }
+    }
+}
+
+fn shared_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Attaches the position on the path to an error from decoding a node.
+fn locate(error: TrieError, node: usize, depth: usize) -> TrieError {
+    match error {
+        TrieError::Decoder(error) => TrieError::BadRlp { node, error },
+        TrieError::InvalidData => TrieError::UnexpectedNode { depth, kind: None },
+        error => error,
+    }
+}
+
+impl<'a, H: TrieHasher> PatriciaTrie<'a, H> {
+    pub fn new(mut nodes: Vec<(H256, &'a [u8])>, root_hash: H256) -> Self {
+        nodes.sort_unstable_by_key(|(hash, _)| *hash);
+        nodes.dedup_by(|a, b| a.0 == b.0);
+        Self {
+            root_hash,
+            nodes,
+            max_depth: DEFAULT_MAX_DEPTH,
+            strict: false,
+            hasher: PhantomData,
+        }
+    }
+
+    /// Caps the number of nodes a lookup may decode, the root included,
+    /// before failing with `TrieError::DepthExceeded`.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn get(&self, key: &[u8]) -> TrieResult<ProofOutcome<'a>> {
+        self.get_traced(key, &mut Vec::new())
+    }
+
+    /// Like `get`, also recording every node visited, in path order. On error
+    /// `trace` holds the nodes visited before the failure.
+    pub fn get_traced(
+        &self,
+        key: &[u8],
+        trace: &mut Vec<TraceStep>,
+    ) -> TrieResult<ProofOutcome<'a>> {
+        let path = &Nibbles::from_raw(key, true);
+        self.get_at(path, trace)
+    }
+
+    pub fn verify_proof<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        proof: &'a [P],
+    ) -> TrieResult<Option<&'a [u8]>> {
+        Self::verify_proof_outcome(root_hash, key, proof).map(ProofOutcome::into_value)
+    }
+
+    /// Verifies the receipt or transaction at `index` against a
+    /// `receiptsRoot` or `transactionsRoot`.
+    pub fn verify_proof_by_index<P: AsRef<[u8]>>(
+        root_hash: H256,
+        index: u64,
+        proof: &'a [P],
+    ) -> TrieResult<Option<&'a [u8]>> {
+        Self::verify_proof(root_hash, &index_key(index), proof)
+    }
+
+    /// Like `verify_proof`, but tells an absent key apart from a present one
+    /// and reports where the key's path left the trie.
+    pub fn verify_proof_outcome<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        proof: &'a [P],
+    ) -> TrieResult<ProofOutcome<'a>> {
+        let trie = Self::from_proof(root_hash, proof);
+        trie.get(key)
+    }
+
+    /// Strict variant of `verify_proof_outcome`: the proof must be exactly the
+    /// hashed nodes on the key's path, root first, with every node under 32
+    /// bytes embedded in its parent.
+    pub fn verify_proof_strict<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        proof: &'a [P],
+    ) -> TrieResult<ProofOutcome<'a>> {
+        let mut nodes = Vec::with_capacity(proof.len());
+        for node_encoded in proof.iter().map(AsRef::as_ref) {
+            let hash = H::hash(node_encoded);
+            if nodes.iter().any(|(h, _)| *h == hash) {
+                return Err(TrieError::DuplicateNode(hash));
+            }
+            if hash != root_hash && node_encoded.len() < HASHED_LENGTH {
+                return Err(TrieError::NonCanonicalInlineNode);
+            }
+            nodes.push((hash, node_encoded));
+        }
+        let order: Vec<H256> = nodes.iter().map(|(hash, _)| *hash).collect();
+
+        let mut trie = Self::new(nodes, root_hash);
+        trie.strict = true;
+        let mut trace = Vec::new();
+        let outcome = trie.get_traced(key, &mut trace)?;
+        let visited: Vec<H256> = trace.iter().filter_map(|step| step.hash).collect();
+
+        for (i, hash) in order.into_iter().enumerate() {
+            if visited.get(i) != Some(&hash) {
+                if visited.contains(&hash) {
+                    return Err(TrieError::OutOfOrderNode(hash));
+                }
+                return Err(TrieError::UnusedNode(hash));
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Like `verify_proof_outcome`, but also returns the nodes visited, so a
+    /// failed proof shows how far verification got.
+    pub fn verify_proof_traced<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        proof: &'a [P],
+    ) -> (Vec<TraceStep>, TrieResult<ProofOutcome<'a>>) {
+        let trie = Self::from_proof(root_hash, proof);
+        let mut trace = Vec::new();
+        let result = trie.get_traced(key, &mut trace);
+        (trace, result)
+    }
+
+    /// Verifies several keys against one shared set of proof nodes, returning
+    /// one outcome per key in the same order.
+    pub fn verify_multiproof<P: AsRef<[u8]>>(
+        root_hash: H256,
+        keys: &[Vec<u8>],
+        proof: &'a [P],
+    ) -> TrieResult<Vec<ProofOutcome<'a>>> {
+        let trie = Self::from_proof(root_hash, proof);
+        keys.iter().map(|key| trie.get(key)).collect()
+    }
+
+    /// Verifies that `keys` and `values` are every entry of the trie with a
+    /// key in `[first, last]`, in trie order. The proof must hold the nodes on
+    /// the paths to `first` and `last`; everything strictly between them is
+    /// rebuilt from the entries. Trie order is by key bytes, so for receipt
+    /// and transaction tries, whose keys are `rlp(index)`, it is not index
+    /// order; `verify_index_range_proof` checks a range of indices.
+    pub fn verify_range_proof<P: AsRef<[u8]>>(
+        root_hash: H256,
+        first: &[u8],
+        last: &[u8],
+        keys: &[Vec<u8>],
+        values: &[Vec<u8>],
+        proof: &'a [P],
+    ) -> TrieResult<()> {
+        if keys.len() != values.len() || first > last {
+            return Err(TrieError::InvalidRange);
+        }
+        let mut entries: Vec<(Nibbles, Vec<u8>)> = Vec::with_capacity(keys.len());
+        for (key, value) in keys.iter().zip(values) {
+            let key = Nibbles::from_raw(key, false);
+            let unordered = entries.last().is_some_and(|(prev, _)| *prev >= key);
+            if unordered || value.is_empty() {
+                return Err(TrieError::InvalidRange);
+            }
+            entries.push((key, value.clone()));
+        }
+        let first = Nibbles::from_raw(first, false);
+        let last = Nibbles::from_raw(last, false);
+        let out_of_range = |(key, _): &(Nibbles, Vec<u8>)| *key < first || *key > last;
+        if entries.first().is_some_and(out_of_range) || entries.last().is_some_and(out_of_range) {
+            return Err(TrieError::InvalidRange);
+        }
+
+        if root_hash == H::hash(&rlp::NULL_RLP) {
+            if !entries.is_empty() {
+                return Err(TrieError::RangeMismatch { depth: 0 });
+            }
+            return Ok(());
+        }
+        let trie = Self::from_proof(root_hash, proof);
+        let root = rlp::encode(&root_hash.as_bytes());
+        let bounds = (first.as_slice(), last.as_slice());
+        trie.check_range(&root, &entries, bounds)
+    }
+
+    /// Verifies that `values` are the receipts or transactions at indices
+    /// `first, first + 1, ...` and that no other index in `[first, last]` is
+    /// in the trie. `[first, last]` is checked as a range proof over each of
+    /// its `index_spans`, so the proof must hold the nodes on the paths to
+    /// both bounds of every span.
+    pub fn verify_index_range_proof<P: AsRef<[u8]>>(
+        root_hash: H256,
+        first: u64,
+        last: u64,
+        values: &[Vec<u8>],
+        proof: &'a [P],
+    ) -> TrieResult<()> {
+        let count = values.len() as u64;
+        if first > last || count > (last - first).saturating_add(1) {
+            return Err(TrieError::InvalidRange);
+        }
+        for (lo, hi) in index_spans(first, last) {
+            let start = (lo - first).min(count);
+            let end = (hi - first).saturating_add(1).min(count);
+            let keys: Vec<Vec<u8>> = (first + start..first + end).map(index_key).collect();
+            let values = &values[start as usize..end as usize];
+            Self::verify_range_proof(
+                root_hash,
+                &index_key(lo),
+                &index_key(hi),
+                &keys,
+                values,
+                proof,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Verifies the proof for `key` down to its leaf and checks the leaf
+    /// against `leaf_hash`, so the proof can leave out a large leaf. A keccak
+    /// cannot be extended from the hash of the value alone, so the commitment
+    /// is the hash of the whole leaf node, as computed by `leaf_hash`. It binds
+    /// the value and the rest of the key.
+    ///
+    /// `rest` is the number of key nibbles held by the leaf. Only the node
+    /// reached after the other nibbles may be missing from the proof, so a
+    /// proof cut short above the leaf fails with `HashMismatch`. Returns the
+    /// leaf's depth in nibbles.
+    pub fn verify_leaf_hash<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        rest: usize,
+        leaf_hash: H256,
+        proof: &'a [P],
+    ) -> TrieResult<usize> {
+        let depth = (key.len() * 2)
+            .checked_sub(rest)
+            .ok_or(TrieError::InvalidData)?;
+        let trie = Self::from_proof(root_hash, proof);
+        let hash = trie.leaf_reference(key, depth)?;
+        if hash != leaf_hash {
+            return Err(TrieError::LeafHashMismatch { depth });
+        }
+        Ok(depth)
+    }
+
+    /// Hash of the leaf node holding `value` for `key`, `depth` nibbles below
+    /// the root. Panics if `depth` exceeds the key's length in nibbles.
+    pub fn leaf_hash(key: &[u8], depth: usize, value: &[u8]) -> H256 {
+        let path = Nibbles::from_raw(key, false);
+        let leaf = Written::Leaf(path.as_slice()[depth..].to_vec(), value.to_vec());
+        H::hash(&leaf.encode())
+    }
+
+    /// Root of the trie after setting `key` to `value` in the trie the proof
+    /// was built from, or after removing `key` if `value` is empty. The proof
+    /// must hold the hashed nodes on the key's path; a removal that leaves a
+    /// branch with a single hashed child also needs that child's node, so it
+    /// can be merged into its parent.
+    pub fn update(&self, key: &[u8], value: &[u8]) -> TrieResult<H256> {
+        let path = Nibbles::from_raw(key, false);
+        let value = (!value.is_empty()).then_some(value);
+        let encoded = self.write(path.as_slice(), value)?.encode();
+        Ok(H::hash(&encoded))
+    }
+
+    /// Root of the trie after removing `key`. Removing an absent key leaves
+    /// the root unchanged.
+    pub fn remove(&self, key: &[u8]) -> TrieResult<H256> {
+        self.update(key, &[])
+    }
+
+    /// Verifies a proof for `key` and returns the root after writing `value`
+    /// there, as in `update`.
+    pub fn update_proof<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        value: &[u8],
+        proof: &'a [P],
+    ) -> TrieResult<H256> {
+        Self::from_proof(root_hash, proof).update(key, value)
+    }
+
+    pub fn from_proof<P: AsRef<[u8]>>(root_hash: H256, proof: &'a [P]) -> Self {
+        let mut nodes = Vec::with_capacity(proof.len());
+        for node_encoded in proof.iter().map(AsRef::as_ref) {
+            let hash = H::hash(node_encoded);
+            if root_hash.eq(&hash) || node_encoded.len() >= HASHED_LENGTH {
+                nodes.push((hash, node_encoded));
+            }
+        }
+        Self::new(nodes, root_hash)
+    }
+
+    fn get_at(&self, path: &Nibbles, trace: &mut Vec<TraceStep>) -> TrieResult<ProofOutcome<'a>> {
+        let (mut node, mut node_hash) = self.root_node()?;
+        let mut path_index = 0;
+        let mut last_hash = self.root_hash;
+        // Position of `node` on the path, counting decoded nodes only.
+        let mut index = 0;
+
+        loop {
+            let partial = &path.offset(path_index);
+            trace.push(TraceStep {
+                hash: node_hash,
+                kind: node.kind(),
+                nibbles: path_index,
+            });
+            if let Some(hash) = node_hash {
+                last_hash = hash;
+            }
+            let absent = ProofOutcome::Absent {
+                divergence_node: last_hash,
+                depth: path_index,
+            };
+            (node, node_hash) = match node {
+                Node::Empty => return Ok(absent),
+                Node::Leaf(leaf) => {
+                    if &leaf.key == partial {
+                        return Ok(ProofOutcome::Present(leaf.value));
+                    } else {
+                        return Ok(absent);
+                    }
+                }
+                Node::Branch(branch) => {
+                    if partial.is_empty() || partial.at(0) == 16 {
+                        let value = branch.value().map_err(|e| locate(e, index, path_index))?;
+                        return match value {
+                            Some(value) => Ok(ProofOutcome::Present(value)),
+                            None => Ok(absent),
+                        };
+                    }
+                    let raw = branch
+                        .child_raw(partial.at(0))
+                        .map_err(|e| locate(e, index, path_index))?;
+                    path_index += 1;
+                    index += 1;
+                    self.load(raw, path_index, index)?
+                }
+                Node::Extension(extension) => {
+                    let prefix = &extension.prefix;
+                    let match_len = partial.common_prefix(prefix);
+                    if match_len != prefix.len() {
+                        return Ok(absent);
+                    }
+                    path_index += match_len;
+                    index += 1;
+                    self.load_target(extension.node, path_index, index)?
+                }
+                Node::Hash(_) => {
+                    return Err(TrieError::UnexpectedNode {
+                        depth: path_index,
+                        kind: Some(NodeKind::Hash),
+                    })
+                }
+            };
+        }
+    }
+
+    /// Walks to the leaf for `key`, which must lie `depth` nibbles below the
+    /// root, and returns its hash. A hashed leaf missing from the proof is
+    /// taken from its parent's reference.
+    fn leaf_reference(&self, key: &[u8], depth: usize) -> TrieResult<H256> {
+        let path = &Nibbles::from_raw(key, true);
+        let (mut node, _) = self.root_node()?;
+        let mut path_index = 0;
+        let mut index = 0;
+
+        loop {
+            let partial = &path.offset(path_index);
+            node = match node {
+                Node::Empty => return Err(TrieError::PathDiverged { nibble: path_index }),
+                Node::Leaf(leaf) => {
+                    if &leaf.key != partial {
+                        return Err(TrieError::PathDiverged { nibble: path_index });
+                    }
+                    if path_index != depth {
+                        return Err(TrieError::UnexpectedNode {
+                            depth: path_index,
+                            kind: Some(NodeKind::Leaf),
+                        });
+                    }
+                    return Ok(Self::leaf_hash(key, path_index, leaf.value));
+                }
+                Node::Branch(branch) => {
+                    if partial.is_empty() || partial.at(0) == 16 {
+                        // The value is held in the branch, not in a leaf.
+                        return Err(TrieError::UnexpectedNode {
+                            depth: path_index,
+                            kind: Some(NodeKind::Branch),
+                        });
+                    }
+                    let raw = branch
+                        .child_raw(partial.at(0))
+                        .map_err(|e| locate(e, index, path_index))?;
+                    path_index += 1;
+                    index += 1;
+                    match self.load(raw, path_index, index) {
+                        Err(TrieError::HashMismatch { expected, .. }) if path_index == depth => {
+                            return Ok(expected)
+                        }
+                        child => child?.0,
+                    }
+                }
+                Node::Extension(extension) => {
+                    let prefix = &extension.prefix;
+                    let match_len = partial.common_prefix(prefix);
+                    if match_len != prefix.len() {
+                        return Err(TrieError::PathDiverged {
+                            nibble: path_index + match_len,
+                        });
+                    }
+                    path_index += match_len;
+                    index += 1;
+                    self.load_target(extension.node, path_index, index)?.0
+                }
+                Node::Hash(_) => {
+                    return Err(TrieError::UnexpectedNode {
+                        depth: path_index,
+                        kind: Some(NodeKind::Hash),
+                    })
+                }
+            };
+        }
+    }
+
+    /// The root node and the hash it was looked up by: empty for the empty
+    /// trie, which needs no proof nodes, otherwise looked up by hash.
+    fn root_node(&self) -> TrieResult<(Node<'a>, Option<H256>)> {
+        if self.root_hash == H::hash(&rlp::NULL_RLP) {
+            return Ok((Node::Empty, None));
+        }
+        let root = self.resolve(self.root_hash, 0, 0)?;
+        Ok((root, Some(self.root_hash)))
+    }
+
+    /// Decodes `raw`, the reference to the `index`-th node on a path, reached
+    /// after `depth` nibbles, and looks it up in the proof if it is a hash.
+    /// Returns the node and the hash it was looked up by. `index` counts
+    /// decoded nodes from the root, so it is bounded by `max_depth`.
+    fn load(
+        &self,
+        raw: &'a [u8],
+        depth: usize,
+        index: usize,
+    ) -> TrieResult<(Node<'a>, Option<H256>)> {
+        if index >= self.max_depth {
+            return Err(TrieError::DepthExceeded);
+        }
+        let node = self
+            .decode_child(raw)
+            .map_err(|e| locate(e, index, depth))?;
+        match node {
+            Node::Hash(hash) => Ok((self.resolve(hash, depth, index)?, Some(hash))),
+            node => Ok((node, None)),
+        }
+    }
+
+    /// Loads the target of an extension like `load`. The target must be a
+    /// branch, embedded or hashed: anything else would have been merged into
+    /// the extension by a canonical trie.
+    fn load_target(
+        &self,
+        raw: &'a [u8],
+        depth: usize,
+        index: usize,
+    ) -> TrieResult<(Node<'a>, Option<H256>)> {
+        let (node, hash) = self.load(raw, depth, index)?;
+        if !matches!(node, Node::Branch(_)) {
+            return Err(TrieError::UnexpectedNode {
+                depth,
+                kind: Some(node.kind()),
+            });
+        }
+        Ok((node, hash))
+    }
+
+    /// Rebuilds the trie with `value` written at `path`, or with `path`
+    /// removed if `value` is `None`. The walk down keeps the nodes above the
+    /// write in `parents`, which are then rebuilt bottom-up, so the call
+    /// stack stays flat however deep the path.
+    fn write(&self, path: &[u8], value: Option<&[u8]>) -> TrieResult<Written> {
+        let (mut node, _) = self.root_node()?;
+        let mut depth = 0;
+        let mut index = 0;
+        let mut parents = Vec::new();
+
+        let mut written = loop {
+            let partial = &path[depth..];
+            match node {
+                Node::Empty => {
+                    break match value {
+                        Some(value) => Written::Leaf(partial.to_vec(), value.to_vec()),
+                        None => Written::Empty,
+                    }
+                }
+                Node::Leaf(leaf) => {
+                    let key = &leaf.key.as_slice()[..leaf.key.len() - 1];
+                    let value = match value {
+                        Some(value) if key != partial => value,
+                        Some(value) => break Written::Leaf(key.to_vec(), value.to_vec()),
+                        None if key == partial => break Written::Empty,
+                        None => break Written::Leaf(key.to_vec(), leaf.value.to_vec()),
+                    };
+
+                    let shared = shared_prefix(key, partial);
+                    let mut children = vec![rlp::NULL_RLP.to_vec(); 16];
+                    let mut branch_value = None;
+                    for (rest, value) in [(&key[shared..], leaf.value), (&partial[shared..], value)]
+                    {
+                        match rest.split_first() {
+                            Some((&nibble, rest)) => {
+                                let leaf = Written::Leaf(rest.to_vec(), value.to_vec());
+                                children[nibble as usize] = leaf.reference::<H>();
+                            }
+                            None => branch_value = Some(value.to_vec()),
+                        }
+                    }
+                    let branch = Written::Branch(children, branch_value);
+                    break Written::with_prefix::<H>(&key[..shared], branch);
+                }
+                Node::Extension(extension) => {
+                    let prefix = extension.prefix.as_slice();
+                    let shared = shared_prefix(prefix, partial);
+                    if shared == prefix.len() {
+                        parents.push(Parent::Extension(prefix.to_vec()));
+                        depth += shared;
+                        index += 1;
+                        (node, _) = self.load_target(extension.node, depth, index)?;
+                        continue;
+                    }
+                    let Some(value) = value else {
+                        break Written::Extension(prefix.to_vec(), extension.node.to_vec());
+                    };
+
+                    let mut children = vec![rlp::NULL_RLP.to_vec(); 16];
+                    let mut branch_value = None;
+                    children[prefix[shared] as usize] = if shared + 1 == prefix.len() {
+                        extension.node.to_vec()
+                    } else {
+                        Written::Extension(prefix[shared + 1..].to_vec(), extension.node.to_vec())
+                            .reference::<H>()
+                    };
+                    match partial[shared..].split_first() {
+                        Some((&nibble, rest)) => {
+                            let leaf = Written::Leaf(rest.to_vec(), value.to_vec());
+                            children[nibble as usize] = leaf.reference::<H>();
+                        }
+                        None => branch_value = Some(value.to_vec()),
+                    }
+                    let branch = Written::Branch(children, branch_value);
+                    break Written::with_prefix::<H>(&prefix[..shared], branch);
+                }
+                Node::Branch(branch) => {
+                    let (children, branch_value) =
+                        Written::branch_parts(&branch).map_err(|e| locate(e, index, depth))?;
+                    let Some((&nibble, _)) = partial.split_first() else {
+                        let value = value.map(<[u8]>::to_vec);
+                        break self.collapse(children, value, None, depth, index)?;
+                    };
+                    let nibble = nibble as usize;
+                    let raw = branch
+                        .child_raw(nibble)
+                        .map_err(|e| locate(e, index, depth))?;
+                    parents.push(Parent::Branch {
+                        children,
+                        value: branch_value,
+                        nibble,
+                        depth,
+                        index,
+                    });
+                    depth += 1;
+                    index += 1;
+                    (node, _) = self.load(raw, depth, index)?;
+                }
+                Node::Hash(_) => {
+                    return Err(TrieError::UnexpectedNode {
+                        depth,
+                        kind: Some(NodeKind::Hash),
+                    })
+                }
+            }
+        };
+
+        while let Some(parent) = parents.pop() {
+            written = match parent {
+                Parent::Extension(prefix) => Written::with_prefix::<H>(&prefix, written),
+                Parent::Branch {
+                    mut children,
+                    value,
+                    nibble,
+                    depth,
+                    index,
+                } => {
+                    children[nibble] = written.reference::<H>();
+                    self.collapse(children, value, Some((nibble, written)), depth, index)?
+                }
+            };
+        }
+        Ok(written)
+    }
+
+    /// Checks the trie referenced by `root` against the range `entries`.
+    /// Subtries inside the range are rebuilt from the entries and compared
+    /// by reference; those on the path to a bound are looked up in the proof
+    /// and checked child by child. Subtries still to check are kept on a
+    /// heap stack, each with its path, its entries, its position on the path
+    /// and whether it is an extension target, so the call stack stays flat
+    /// however deep the trie.
+    fn check_range(
+        &self,
+        root: &'a [u8],
+        entries: &[(Nibbles, Vec<u8>)],
+        (first, last): (&[u8], &[u8]),
+    ) -> TrieResult<()> {
+        let in_range = |path: &[u8]| first <= path && path <= last;
+        let mut pending = vec![(root, Vec::new(), entries, 0, false)];
+
+        while let Some((raw, path, entries, index, target)) = pending.pop() {
+            let depth = path.len();
+            let mismatch = Err(TrieError::RangeMismatch { depth });
+
+            match Span::of(&path, first, last) {
+                // Entries are all in the range, so none can be under `path`.
+                Span::Outside => continue,
+                Span::Inside if PatriciaTrieBuilder::<H>::reference(entries, depth) == raw => {
+                    continue
+                }
+                Span::Inside => return mismatch,
+                Span::Edge => {}
+            }
+
+            let (node, _) = if target {
+                self.load_target(raw, depth, index)?
+            } else {
+                self.load(raw, depth, index)?
+            };
+            match node {
+                Node::Empty if entries.is_empty() => {}
+                Node::Leaf(leaf) => {
+                    let key = leaf.key.as_slice();
+                    let key = [&path, &key[..key.len() - 1]].concat();
+                    match entries {
+                        [] if !in_range(&key) => {}
+                        [(entry, value)] if entry.as_slice() == key && value == leaf.value => {}
+                        _ => return mismatch,
+                    }
+                }
+                Node::Extension(extension) => {
+                    let path = [&path, extension.prefix.as_slice()].concat();
+                    if !entries
+                        .iter()
+                        .all(|(key, _)| key.as_slice().starts_with(&path))
+                    {
+                        return mismatch;
+                    }
+                    pending.push((extension.node, path, entries, index + 1, true));
+                }
+                Node::Branch(branch) => {
+                    let (here, mut rest) = match entries.split_first() {
+                        Some((entry, rest)) if entry.0.len() == depth => (Some(&entry.1), rest),
+                        _ => (None, entries),
+                    };
+                    let value = branch.value().map_err(|e| locate(e, index, depth))?;
+                    match (value, here) {
+                        (Some(value), Some(entry)) if value == entry.as_slice() => {}
+                        (Some(_), None) if !in_range(&path) => {}
+                        (None, None) => {}
+                        _ => return mismatch,
+                    }
+                    let mut children = Vec::with_capacity(16);
+                    for nibble in 0..16 {
+                        let count = rest
+                            .iter()
+                            .take_while(|(key, _)| key.at(depth) == nibble)
+                            .count();
+                        let child = branch
+                            .child_raw(nibble)
+                            .map_err(|e| locate(e, index, depth))?;
+                        let path = [&path, &[nibble as u8][..]].concat();
+                        children.push((child, path, &rest[..count], index + 1, false));
+                        rest = &rest[count..];
+                    }
+                    // Popped in nibble order.
+                    pending.extend(children.into_iter().rev());
+                }
+                _ => return mismatch,
+            }
+        }
+        Ok(())
+    }
+
+    /// Normalizes a branch, the `index`-th node on the path, after a write. A
+    /// branch left with only its value becomes a leaf, and one left with a
+    /// single child is merged into that child, which is loaded from the proof
+    /// unless it is the `written` one.
+    fn collapse(
+        &self,
+        children: Vec<Vec<u8>>,
+        value: Option<Vec<u8>>,
+        written: Option<(usize, Written)>,
+        depth: usize,
+        index: usize,
+    ) -> TrieResult<Written> {
+        let mut used = (0..16).filter(|&i| children[i] != rlp::NULL_RLP);
+        let only = match (used.next(), used.next(), value) {
+            (None, _, None) => return Ok(Written::Empty),
+            (None, _, Some(value)) => return Ok(Written::Leaf(Vec::new(), value)),
+            (Some(index), None, None) => index,
+            (_, _, value) => return Ok(Written::Branch(children, value)),
+        };
+
+        let child = match written {
+            Some((index, child)) if index == only => child,
+            _ => {
+                let (node, _) = self.load(&children[only], depth + 1, index + 1)?;
+                Written::from_node(&node).map_err(|e| locate(e, index + 1, depth + 1))?
+            }
+        };
+        Ok(Written::with_prefix::<H>(&[only as u8], child))
+    }
+
+    /// Looks up the `index`-th node on the path by hash in the proof.
+    fn resolve(&self, hash: H256, depth: usize, index: usize) -> TrieResult<Node<'a>> {
+        let node = self
+            .recover_from_db(hash)
+            .map_err(|e| locate(e, index, depth))?
+            .ok_or(TrieError::HashMismatch {
+                depth,
+                expected: hash,
+            })?;
+        if let Node::Hash(_) | Node::Empty = node {
+            return Err(TrieError::UnexpectedNode {
+                depth,
+                kind: Some(node.kind()),
+            });
+        }
+        Ok(node)
+    }
+
+    /// Decodes a branch or extension child. In strict mode an embedded child
+    /// must encode to under 32 bytes, otherwise it should have been hashed.
+    fn decode_child(&self, raw: &'a [u8]) -> TrieResult<Node<'a>> {
+        if self.strict && raw.len() >= HASHED_LENGTH && Rlp::new(raw).is_list() {
+            return Err(TrieError::NonCanonicalInlineNode);
+        }
+        Node::decode(raw)
+    }
+
+    fn recover_from_db(&self, key: H256) -> TrieResult<Option<Node<'a>>> {
+        match self.nodes.binary_search_by(|(hash, _)| hash.cmp(&key)) {
+            Ok(i) => Ok(Some(Node::decode(self.nodes[i].1)?)),
+            Err(_) => Ok(None),
+        }
+    }
+}
+
+/// Verifiers for Ethereum's state and storage tries, which are keccak tries
+/// keyed by the keccak of the address or slot.
+impl<'a> PatriciaTrie<'a, Keccak> {
+    /// Secure-trie variant of `verify_proof_outcome` for the state and storage
+    /// tries, which are keyed by the hash of the key rather than the raw key.
+    pub fn verify_secure_proof<P: AsRef<[u8]>>(
+        root_hash: H256,
+        key: &[u8],
+        proof: &'a [P],
+    ) -> TrieResult<ProofOutcome<'a>> {
+        Self::verify_proof_outcome(root_hash, Keccak::hash(key).as_bytes(), proof)
+    }
+
+    /// Verifies an `eth_getProof` account proof against a state root and
+    /// decodes the account leaf. Returns `None` if the account does not exist.
+    pub fn verify_account_proof<P: AsRef<[u8]>>(
+        state_root: H256,
+        address: &[u8; 20],
+        proof: &'a [P],
+    ) -> TrieResult<Option<EthAccount>> {
+        match Self::verify_secure_proof(state_root, address, proof)? {
+            ProofOutcome::Present(value) => Ok(Some(rlp::decode(value)?)),
+            ProofOutcome::Absent { .. } => Ok(None),
+        }
+    }
+
+    /// Verifies a storage proof for `slot` against an account's storage root.
+    /// Slots missing from the trie read as zero.
+    pub fn verify_storage_slot<P: AsRef<[u8]>>(
+        storage_root: H256,
+        slot: &[u8; 32],
+        proof: &'a [P],
+    ) -> TrieResult<H256> {
+        match Self::verify_secure_proof(storage_root, slot, proof)? {
+            ProofOutcome::Present(value) => Ok(decode_storage_value(value)?),
+            ProofOutcome::Absent { .. } => Ok(H256::zero()),
+        }
+    }
+
+    /// Verifies the account proof for `address` against `state_root`, then
+    /// the storage proof for `slot` against that account's storage root.
+    pub fn verify_storage_proof<P: AsRef<[u8]>>(
+        state_root: H256,
+        address: &[u8; 20],
+        account_proof: &'a [P],
+        slot: &[u8; 32],
+        storage_proof: &'a [P],
+    ) -> TrieResult<H256> {
+        match Self::verify_account_proof(state_root, address, account_proof)? {
+            Some(account) => Self::verify_storage_slot(account.storage_root, slot, storage_proof),
+            None => Ok(H256::zero()),
+        }
+    }
+}
@@ -0,0 +1,687 @@
+//! Conformance tests for the trie verifier: the ethereum/tests trie vectors
+//! under `tests/fixtures`, and a randomized differential check against
+//! `eth_trie`, the trie the CLI builds proofs with.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use eth_trie::{EthTrie as RefTrie, MemoryDB, Trie, DB};
+use mpt_core::builder::EthTrieBuilder;
+use mpt_core::errors::TrieError;
+use mpt_core::hasher::{Keccak, TrieHasher};
+use mpt_core::proof::CompactProof;
+use mpt_core::trie::{index_key, index_spans, EthTrie, ProofOutcome};
+use mpt_core::H256;
+use serde_json::Value;
+
+/// A trie vector: writes applied in order (`None` deletes) and the root.
+struct Vector {
+    name: String,
+    writes: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+    root: H256,
+}
+
+/// Fixture strings are hex when `0x`-prefixed, raw bytes otherwise.
+fn fixture_bytes(value: &str) -> Vec<u8> {
+    match value.strip_prefix("0x") {
+        Some(hex) => hex::decode(hex).unwrap(),
+        None => value.as_bytes().to_vec(),
+    }
+}
+
+fn load_vectors(file: &str) -> Vec<Vector> {
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), file);
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+
+    let mut vectors = Vec::new();
+    for (name, test) in json.as_object().unwrap() {
+        let write =
+            |key: &str, value: &Value| (fixture_bytes(key), value.as_str().map(fixture_bytes));
+        // trietest.json lists writes in order, trieanyorder.json as a map.
+        let writes = match &test["in"] {
+            Value::Array(pairs) => pairs
+                .iter()
+                .map(|pair| write(pair[0].as_str().unwrap(), &pair[1]))
+                .collect(),
+            Value::Object(pairs) => pairs.iter().map(|(key, value)| write(key, value)).collect(),
+            _ => panic!("{}: bad input", name),
+        };
+        let root = H256::from_slice(&fixture_bytes(test["root"].as_str().unwrap()));
+        vectors.push(Vector {
+            name: name.clone(),
+            writes,
+            root,
+        });
+    }
+    vectors
+}
+
+fn root_of(trie: &mut RefTrie<MemoryDB>) -> H256 {
+    H256::from(trie.root_hash().unwrap().to_fixed_bytes())
+}
+
+#[test]
+fn ethereum_trie_vectors() {
+    let mut vectors = load_vectors("trietest.json");
+    vectors.extend(load_vectors("trieanyorder.json"));
+    assert!(!vectors.is_empty());
+
+    for vector in vectors {
+        let mut builder = EthTrieBuilder::new();
+        let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+        let mut expected: BTreeMap<Vec<u8>, Vec<u8>> = BTreeMap::new();
+        for (key, value) in &vector.writes {
+            builder.insert(key, value.clone().unwrap_or_default());
+            match value {
+                Some(value) => {
+                    reference.insert(key, value).unwrap();
+                    expected.insert(key.clone(), value.clone());
+                }
+                None => {
+                    reference.remove(key).unwrap();
+                    expected.remove(key);
+                }
+            }
+        }
+        assert_eq!(builder.root(), vector.root, "{}: builder root", vector.name);
+        let root = root_of(&mut reference);
+        assert_eq!(root, vector.root, "{}: eth_trie root", vector.name);
+
+        for (key, _) in &vector.writes {
+            let proof = reference.get_proof(key).unwrap();
+            let value = EthTrie::verify_proof(root, key, &proof).unwrap();
+            assert_eq!(
+                value,
+                expected.get(key).map(Vec::as_slice),
+                "{}: key {}",
+                vector.name,
+                hex::encode(key)
+            );
+        }
+    }
+}
+
+/// Deterministic xorshift generator, so failures reproduce.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> usize {
+        (self.next() % n) as usize
+    }
+
+    fn bytes(&mut self, len: usize, alphabet: u64) -> Vec<u8> {
+        (0..len).map(|_| self.below(alphabet) as u8).collect()
+    }
+}
+
+/// Trie shapes the harness draws from.
+#[derive(Clone, Copy, Debug)]
+enum Shape {
+    /// Short keys over a tiny alphabet with one-byte values: keys are often
+    /// prefixes of each other, giving branch values, and most nodes are
+    /// under 32 bytes, so they are embedded in their parent.
+    Tiny,
+    /// 32-byte hashed keys with short values, as in the state trie.
+    Secure,
+    /// `rlp(index)` keys with values of any size, as in the receipt trie.
+    Indexed,
+}
+
+impl Shape {
+    /// `eth_trie` mishandles the empty key, so keys are never empty.
+    fn key(self, rng: &mut Rng) -> Vec<u8> {
+        match self {
+            Shape::Tiny => {
+                let len = 1 + rng.below(3);
+                rng.bytes(len, 3)
+            }
+            Shape::Secure => rng.bytes(32, 256),
+            Shape::Indexed => rlp::encode(&(rng.below(300) as u64)).to_vec(),
+        }
+    }
+
+    fn value(self, rng: &mut Rng) -> Vec<u8> {
+        let len = match self {
+            Shape::Tiny => 1,
+            Shape::Secure => 1 + rng.below(8),
+            Shape::Indexed => 1 + rng.below(100),
+        };
+        rng.bytes(len, 256)
+    }
+}
+
+#[test]
+fn differential_against_eth_trie() {
+    let mut rng = Rng(0x5eed_1234_abcd_ef01);
+    let shapes = [Shape::Tiny, Shape::Secure, Shape::Indexed];
+
+    for round in 0..300 {
+        let shape = shapes[round % shapes.len()];
+        let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+        let mut builder = EthTrieBuilder::new();
+        let mut entries = BTreeMap::new();
+        for _ in 0..1 + rng.below(48) {
+            let (key, value) = (shape.key(&mut rng), shape.value(&mut rng));
+            reference.insert(&key, &value).unwrap();
+            builder.insert(&key, value.clone());
+            entries.insert(key, value);
+        }
+        let root = root_of(&mut reference);
+        let reference_root = reference.root_hash().unwrap();
+        assert_eq!(builder.root(), root, "round {}: builder root", round);
+
+        let present = entries.keys().cloned();
+        let absent = (0..4).map(|_| shape.key(&mut rng));
+        for key in present.chain(absent).collect::<Vec<_>>() {
+            let context = format!("round {} {:?} key {}", round, shape, hex::encode(&key));
+            let proof = reference.get_proof(&key).unwrap();
+
+            let ours = EthTrie::verify_proof(root, &key, &proof).unwrap();
+            let theirs = reference
+                .verify_proof(reference_root, &key, proof.clone())
+                .unwrap();
+            assert_eq!(ours, theirs.as_deref(), "{}", context);
+            assert_eq!(ours, entries.get(&key).map(Vec::as_slice), "{}", context);
+
+            // Every node in an `eth_trie` proof is on the key's path, so
+            // dropping or altering any of them must fail both verifiers.
+            let mut truncated = proof.clone();
+            truncated.remove(rng.below(proof.len() as u64));
+            assert!(
+                EthTrie::verify_proof(root, &key, &truncated).is_err(),
+                "{}",
+                context
+            );
+
+            let mut tampered = proof.clone();
+            let node = &mut tampered[rng.below(proof.len() as u64)];
+            let byte = rng.below(node.len() as u64);
+            node[byte] ^= 1 + rng.below(255) as u8;
+            assert!(
+                EthTrie::verify_proof(root, &key, &tampered).is_err(),
+                "{}",
+                context
+            );
+            let theirs = reference.verify_proof(reference_root, &key, tampered);
+            assert!(
+                theirs.is_err(),
+                "{}: eth_trie accepted tampered proof",
+                context
+            );
+
+            let wrong_root = H256::from(rng.bytes(32, 256).try_into().unwrap_or([0; 32]));
+            assert!(
+                EthTrie::verify_proof(wrong_root, &key, &proof).is_err(),
+                "{}",
+                context
+            );
+        }
+    }
+}
+
+/// `eth_trie` holding `entries`, built by inserts alone: its `remove` gets
+/// the root wrong when deleting a value held by a branch, so the update
+/// tests rebuild the reference trie rather than edit it.
+fn reference_trie(entries: &BTreeMap<Vec<u8>, Vec<u8>>) -> (RefTrie<MemoryDB>, Arc<MemoryDB>) {
+    let db = Arc::new(MemoryDB::new(false));
+    let mut trie = RefTrie::new(db.clone());
+    for (key, value) in entries {
+        trie.insert(key, value).unwrap();
+    }
+    (trie, db)
+}
+
+/// Writes `value` at `key` in `entries`, removing `key` if `value` is empty,
+/// and checks `EthTrie::update_proof` against the new `eth_trie` root. A
+/// removal that merges a branch into its only remaining, hashed child also
+/// needs that child, which is added to the proof when reported missing.
+fn check_update(entries: &mut BTreeMap<Vec<u8>, Vec<u8>>, key: &[u8], value: &[u8]) {
+    let (mut trie, db) = reference_trie(entries);
+    let root = root_of(&mut trie);
+    let mut proof = trie.get_proof(key).unwrap();
+    let ours = loop {
+        match EthTrie::update_proof(root, key, value, &proof) {
+            Err(TrieError::HashMismatch { expected, .. }) if value.is_empty() => {
+                proof.push(db.get(expected.as_bytes()).unwrap().unwrap());
+            }
+            result => break result.unwrap(),
+        }
+    };
+
+    if value.is_empty() {
+        entries.remove(key);
+    } else {
+        entries.insert(key.to_vec(), value.to_vec());
+    }
+    let (mut updated, _) = reference_trie(entries);
+    assert_eq!(ours, root_of(&mut updated), "write {}", hex::encode(key));
+}
+
+#[test]
+fn update_proof_against_eth_trie() {
+    let mut entries = BTreeMap::new();
+    let (mut trie, _) = reference_trie(&entries);
+
+    // The empty trie needs no proof nodes, to read or to write.
+    let empty = root_of(&mut trie);
+    let outcome = EthTrie::verify_proof_outcome::<Vec<u8>>(empty, b"\x12", &[]);
+    assert!(matches!(outcome, Ok(ProofOutcome::Absent { depth: 0, .. })));
+    // Insert into the empty trie, then overwrite.
+    check_update(&mut entries, b"\x12\x34", &[1; 40]);
+    check_update(&mut entries, b"\x12\x34", &[2; 40]);
+    // Split the leaf's path into an extension over a branch, then split the
+    // extension.
+    check_update(&mut entries, b"\x12\x35", &[3; 40]);
+    check_update(&mut entries, b"\x13\x00", b"a");
+    // Delete a missing key, which leaves the trie as it is.
+    let before = entries.clone();
+    check_update(&mut entries, b"\x12\x36", &[]);
+    assert_eq!(entries, before);
+    // Deleting collapses the branches: first into a hashed leaf that is not
+    // on the key's path, then into an embedded one.
+    check_update(&mut entries, b"\x12\x35", &[]);
+    check_update(&mut entries, b"\x12\x34", &[]);
+    check_update(&mut entries, b"\x13\x00", &[]);
+    assert!(entries.is_empty());
+
+    let mut rng = Rng(0x0b5e_55ed);
+    let shapes = [Shape::Tiny, Shape::Secure, Shape::Indexed];
+    for round in 0..60 {
+        let shape = shapes[round % shapes.len()];
+        let mut entries = BTreeMap::new();
+        for _ in 0..1 + rng.below(48) {
+            // Half the writes hit a known key, so overwrites and deletes of
+            // present keys are as common as inserts and deletes of missing
+            // ones.
+            let known: Vec<&Vec<u8>> = entries.keys().collect();
+            let key = match known.len() {
+                n if n > 0 && rng.below(2) == 0 => known[rng.below(n as u64)].clone(),
+                _ => shape.key(&mut rng),
+            };
+            let value = match rng.below(3) {
+                0 => Vec::new(),
+                _ => shape.value(&mut rng),
+            };
+            check_update(&mut entries, &key, &value);
+        }
+    }
+}
+
+#[test]
+fn range_proof_against_eth_trie() {
+    let mut rng = Rng(0x7a_4e5e);
+    for shape in [Shape::Secure, Shape::Indexed] {
+        let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+        let mut entries = BTreeMap::new();
+        for _ in 0..200 {
+            let (key, value) = (shape.key(&mut rng), shape.value(&mut rng));
+            reference.insert(&key, &value).unwrap();
+            entries.insert(key, value);
+        }
+        let root = root_of(&mut reference);
+
+        for _ in 0..40 {
+            let (mut first, mut last) = (shape.key(&mut rng), shape.key(&mut rng));
+            if first > last {
+                std::mem::swap(&mut first, &mut last);
+            }
+            let mut proof = reference.get_proof(&first).unwrap();
+            proof.extend(reference.get_proof(&last).unwrap());
+            let range: Vec<(Vec<u8>, Vec<u8>)> = entries
+                .range(first.clone()..=last.clone())
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+            let verify = |range: &[(Vec<u8>, Vec<u8>)]| {
+                let (keys, values): (Vec<_>, Vec<_>) = range.iter().cloned().unzip();
+                EthTrie::verify_range_proof(root, &first, &last, &keys, &values, &proof)
+            };
+
+            assert_eq!(verify(&range), Ok(()));
+            if first < last {
+                let result = EthTrie::verify_range_proof(root, &last, &first, &[], &[], &proof);
+                assert_eq!(result, Err(TrieError::InvalidRange));
+            }
+
+            if !range.is_empty() {
+                let mut missing = range.clone();
+                missing.remove(rng.below(range.len() as u64));
+                assert!(matches!(
+                    verify(&missing),
+                    Err(TrieError::RangeMismatch { .. })
+                ));
+            }
+
+            let extra = (0..100)
+                .map(|_| shape.key(&mut rng))
+                .find(|key| first <= *key && *key <= last && !entries.contains_key(key));
+            if let Some(extra) = extra {
+                let mut extended = range.clone();
+                extended.push((extra, vec![1]));
+                extended.sort();
+                assert!(matches!(
+                    verify(&extended),
+                    Err(TrieError::RangeMismatch { .. })
+                ));
+            }
+        }
+
+        // A range over a single missing key holds no entries, while one
+        // over a present key must list it.
+        let absent = (0..100)
+            .map(|_| shape.key(&mut rng))
+            .find(|key| !entries.contains_key(key))
+            .unwrap();
+        let proof = reference.get_proof(&absent).unwrap();
+        assert_eq!(
+            EthTrie::verify_range_proof(root, &absent, &absent, &[], &[], &proof),
+            Ok(())
+        );
+        let (present, value) = entries.iter().next().unwrap();
+        let proof = reference.get_proof(present).unwrap();
+        assert!(matches!(
+            EthTrie::verify_range_proof(root, present, present, &[], &[], &proof),
+            Err(TrieError::RangeMismatch { .. })
+        ));
+        assert_eq!(
+            EthTrie::verify_range_proof(
+                root,
+                present,
+                present,
+                std::slice::from_ref(present),
+                std::slice::from_ref(value),
+                &proof
+            ),
+            Ok(())
+        );
+    }
+}
+
+#[test]
+fn index_range_proof_against_eth_trie() {
+    assert_eq!(
+        index_spans(0, 300),
+        [(0, 0), (1, 127), (128, 255), (256, 300)]
+    );
+    assert_eq!(index_spans(200, 200), [(200, 200)]);
+    assert_eq!(
+        index_spans(1 << 48, u64::MAX),
+        [(1 << 48, (1 << 56) - 1), (1 << 56, u64::MAX)]
+    );
+
+    let mut rng = Rng(0x1d_7a4e);
+    let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    let values: Vec<Vec<u8>> = (0..300)
+        .map(|index| {
+            let value = Shape::Indexed.value(&mut rng);
+            reference.insert(&index_key(index), &value).unwrap();
+            value
+        })
+        .collect();
+    let root = root_of(&mut reference);
+
+    for (first, last) in [(0, 299), (0, 0), (5, 5), (100, 260), (250, 400), (300, 500)] {
+        let mut proof = Vec::new();
+        for (lo, hi) in index_spans(first, last) {
+            proof.extend(reference.get_proof(&index_key(lo)).unwrap());
+            proof.extend(reference.get_proof(&index_key(hi)).unwrap());
+        }
+        let held = &values[(first as usize).min(300)..(last as usize + 1).min(300)];
+        let verify = |values: &[Vec<u8>]| {
+            EthTrie::verify_index_range_proof(root, first, last, values, &proof)
+        };
+
+        assert_eq!(verify(held), Ok(()));
+        if !held.is_empty() {
+            // Dropping the last receipt leaves it unaccounted for.
+            assert!(matches!(
+                verify(&held[..held.len() - 1]),
+                Err(TrieError::RangeMismatch { .. })
+            ));
+        }
+        if held.len() > 1 {
+            let mut swapped = held.to_vec();
+            swapped.swap(0, held.len() - 1);
+            assert!(verify(&swapped).is_err());
+        }
+    }
+
+    let proof = reference.get_proof(&index_key(5)).unwrap();
+    assert_eq!(
+        EthTrie::verify_index_range_proof(root, 6, 5, &[], &proof),
+        Err(TrieError::InvalidRange)
+    );
+    assert_eq!(
+        EthTrie::verify_index_range_proof(root, 5, 5, &values[5..7], &proof),
+        Err(TrieError::InvalidRange)
+    );
+}
+
+/// A secure trie, a key with a proof of at least three nodes, and a node of
+/// another key's proof that is not on the key's path.
+fn strict_case() -> (H256, Vec<u8>, Vec<Vec<u8>>, Vec<u8>) {
+    let mut rng = Rng(0x57_41c7);
+    let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    let mut keys = Vec::new();
+    for _ in 0..200 {
+        let (key, value) = (Shape::Secure.key(&mut rng), Shape::Secure.value(&mut rng));
+        reference.insert(&key, &value).unwrap();
+        keys.push(key);
+    }
+    let root = root_of(&mut reference);
+
+    let proof = reference.get_proof(&keys[0]).unwrap();
+    assert!(proof.len() >= 3);
+    let unrelated = keys[1..]
+        .iter()
+        .flat_map(|key| reference.get_proof(key).unwrap())
+        .find(|node| !proof.contains(node))
+        .unwrap();
+    assert_eq!(
+        EthTrie::verify_proof_strict(root, &keys[0], &proof).map(|o| o.into_value().is_some()),
+        Ok(true)
+    );
+    (root, keys.swap_remove(0), proof, unrelated)
+}
+
+#[test]
+fn strict_rejects_unused_node() {
+    let (root, key, mut proof, unrelated) = strict_case();
+    proof.push(unrelated.clone());
+    assert_eq!(
+        EthTrie::verify_proof_strict(root, &key, &proof),
+        Err(TrieError::UnusedNode(Keccak::hash(&unrelated)))
+    );
+    assert!(EthTrie::verify_proof(root, &key, &proof).unwrap().is_some());
+}
+
+#[test]
+fn strict_rejects_duplicate_node() {
+    let (root, key, mut proof, _) = strict_case();
+    proof.insert(2, proof[1].clone());
+    assert_eq!(
+        EthTrie::verify_proof_strict(root, &key, &proof),
+        Err(TrieError::DuplicateNode(Keccak::hash(&proof[1])))
+    );
+    assert!(EthTrie::verify_proof(root, &key, &proof).unwrap().is_some());
+}
+
+#[test]
+fn strict_rejects_out_of_order_node() {
+    let (root, key, mut proof, _) = strict_case();
+    proof.swap(1, 2);
+    assert_eq!(
+        EthTrie::verify_proof_strict(root, &key, &proof),
+        Err(TrieError::OutOfOrderNode(Keccak::hash(&proof[1])))
+    );
+    assert!(EthTrie::verify_proof(root, &key, &proof).unwrap().is_some());
+}
+
+#[test]
+fn strict_rejects_hashed_short_node() {
+    // Two leaves small enough to be embedded in the root branch.
+    let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    reference.insert(&[0x10], &[1]).unwrap();
+    reference.insert(&[0x20], &[2]).unwrap();
+    let root_node = reference.get_proof(&[0x10]).unwrap().remove(0);
+
+    // Reference the first leaf by hash instead, and prove it separately.
+    let branch = rlp::Rlp::new(&root_node);
+    let leaf = branch.at(1).unwrap().as_raw().to_vec();
+    assert!(leaf.len() < 32);
+    let mut stream = rlp::RlpStream::new_list(17);
+    for (nibble, child) in branch.iter().enumerate() {
+        match nibble {
+            1 => stream.append(&Keccak::hash(&leaf).as_bytes()),
+            _ => stream.append_raw(child.as_raw(), 1),
+        };
+    }
+    let proof = vec![stream.out().to_vec(), leaf];
+    let root = Keccak::hash(&proof[0]);
+
+    assert_eq!(
+        EthTrie::verify_proof_strict(root, &[0x10], &proof),
+        Err(TrieError::NonCanonicalInlineNode)
+    );
+}
+
+#[test]
+fn compact_proof_round_trip() {
+    let mut rng = Rng(0x0dd_c0de);
+    let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    let mut keys = Vec::new();
+    for _ in 0..64 {
+        let (key, value) = (Shape::Indexed.key(&mut rng), Shape::Indexed.value(&mut rng));
+        reference.insert(&key, &value).unwrap();
+        keys.push(key);
+    }
+    let root = root_of(&mut reference);
+
+    // A multiproof repeats the nodes near the root once per key.
+    let nodes: Vec<Vec<u8>> = keys
+        .iter()
+        .flat_map(|key| reference.get_proof(key).unwrap())
+        .collect();
+    let encoded = CompactProof::encode(root, &[], &nodes).unwrap();
+    let plain: usize = nodes.iter().map(|node| 4 + node.len()).sum();
+    assert!(encoded.len() < plain);
+
+    let proof = CompactProof::decode(&encoded).unwrap();
+    assert_eq!((proof.root, proof.key), (root, &[][..]));
+    // Each node is kept once, at its first position.
+    let mut unique: Vec<&[u8]> = Vec::new();
+    for node in &nodes {
+        if !unique.contains(&node.as_slice()) {
+            unique.push(node);
+        }
+    }
+    assert!(unique.len() < nodes.len());
+    assert_eq!(proof.nodes, unique);
+    let outcomes = EthTrie::verify_multiproof(root, &keys, &proof.nodes).unwrap();
+    assert!(outcomes
+        .iter()
+        .all(|outcome| matches!(outcome, ProofOutcome::Present(_))));
+
+    for len in 0..encoded.len() {
+        assert_eq!(
+            CompactProof::decode(&encoded[..len]),
+            Err(TrieError::BadProofEncoding)
+        );
+    }
+    let mut trailing = encoded.clone();
+    trailing.push(0);
+    assert_eq!(
+        CompactProof::decode(&trailing),
+        Err(TrieError::BadProofEncoding)
+    );
+
+    // A single-key proof carries its key, and a truncated key is caught.
+    let single = reference.get_proof(&keys[0]).unwrap();
+    let encoded = CompactProof::encode(root, &keys[0], &single).unwrap();
+    let proof = CompactProof::decode(&encoded).unwrap();
+    assert_eq!((proof.root, proof.key), (root, keys[0].as_slice()));
+    assert!(proof.nodes.iter().eq(&single));
+    for len in 0..35 + keys[0].len() {
+        assert_eq!(
+            CompactProof::decode(&encoded[..len]),
+            Err(TrieError::BadProofEncoding)
+        );
+    }
+    assert_eq!(
+        CompactProof::encode(root, &[0; 1 << 16], &single),
+        Err(TrieError::BadProofEncoding)
+    );
+}
+
+#[test]
+fn leaf_hash_without_leaf() {
+    let mut rng = Rng(0x1eaf);
+    let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    let mut entries = BTreeMap::new();
+    for _ in 0..64 {
+        let (key, value) = (Shape::Indexed.key(&mut rng), Shape::Indexed.value(&mut rng));
+        reference.insert(&key, &value).unwrap();
+        entries.insert(key, value);
+    }
+    let root = root_of(&mut reference);
+
+    let (mut hashed, mut truncated) = (0, 0);
+    for (key, value) in &entries {
+        let mut proof = reference.get_proof(key).unwrap();
+        let (trace, _) = EthTrie::verify_proof_traced(root, key, &proof);
+        let depth = trace.last().unwrap().nibbles;
+        let rest = key.len() * 2 - depth;
+        let leaf_hash = EthTrie::leaf_hash(key, depth, value);
+        assert_eq!(
+            EthTrie::verify_leaf_hash(root, key, rest, leaf_hash, &proof),
+            Ok(depth)
+        );
+        let mut other = value.clone();
+        other.push(0);
+        let other_hash = EthTrie::leaf_hash(key, depth, &other);
+        assert_eq!(
+            EthTrie::verify_leaf_hash(root, key, rest, other_hash, &proof),
+            Err(TrieError::LeafHashMismatch { depth })
+        );
+        assert!(matches!(
+            EthTrie::verify_leaf_hash(root, key, rest + 1, leaf_hash, &proof),
+            Err(TrieError::UnexpectedNode { .. })
+        ));
+
+        // A hashed leaf is the last proof node and can be left out.
+        if Keccak::hash(proof.last().unwrap()) != leaf_hash {
+            continue;
+        }
+        proof.pop();
+        assert_eq!(
+            EthTrie::verify_leaf_hash(root, key, rest, leaf_hash, &proof),
+            Ok(depth)
+        );
+        hashed += 1;
+
+        // The branch above it cannot be left out in its place.
+        if proof.len() > 1 {
+            let branch = proof.pop().unwrap();
+            let branch_hash = Keccak::hash(&branch);
+            assert!(matches!(
+                EthTrie::verify_leaf_hash(root, key, rest, branch_hash, &proof),
+                Err(TrieError::HashMismatch { expected, .. }) if expected == branch_hash
+            ));
+            truncated += 1;
+        }
+    }
+    assert!(hashed > 0 && truncated > 0);
+
+    let absent = rlp::encode(&1000u64).to_vec();
+    let proof = reference.get_proof(&absent).unwrap();
+    assert!(matches!(
+        EthTrie::verify_leaf_hash(root, &absent, 1, H256::zero(), &proof),
+        Err(TrieError::PathDiverged { .. })
+    ));
+}
@@ -0,0 +1,105 @@
+//! Tries hashed with each `TrieHasher`. Keccak and the pure-Rust SHA3-256
+//! below are checked in every build; `Sha256` and `Blake3` exist only with the
+//! `solana` feature, which `cargo test --workspace` enables through the
+//! program crate.
+
+use mpt_core::builder::PatriciaTrieBuilder;
+use mpt_core::errors::TrieError;
+#[cfg(feature = "solana")]
+use mpt_core::hasher::{Blake3, Sha256};
+use mpt_core::hasher::{Keccak, TrieHasher};
+use mpt_core::trie::PatriciaTrie;
+use mpt_core::H256;
+use rlp::RlpStream;
+use tiny_keccak::{Hasher, Sha3};
+
+/// SHA3-256, a hasher defined outside the crate.
+struct Sha3Hasher;
+
+impl TrieHasher for Sha3Hasher {
+    fn hash(data: &[u8]) -> H256 {
+        let mut output = [0u8; 32];
+        let mut sha3 = Sha3::v256();
+        sha3.update(data);
+        sha3.finalize(&mut output);
+        H256(output)
+    }
+}
+
+fn leaf(compact_path: &[u8], value: &[u8]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(2);
+    stream.append(&compact_path).append(&value);
+    stream.out().to_vec()
+}
+
+/// Builds by hand a branch over two hashed leaves, for keys `12 34` and
+/// `56 78`, and checks proofs and updates against the builder's roots.
+/// `Other` is a different hasher, under which the proof must not verify.
+fn check_hand_built_trie<H: TrieHasher, Other: TrieHasher>() {
+    let (first, second) = ([0x12, 0x34], [0x56, 0x78]);
+    let values = [vec![0xaa; 40], vec![0xbb; 40]];
+    // Leaves for the odd-length rests `2 3 4` and `6 7 8`.
+    let leaves = [
+        leaf(&[0x32, 0x34], &values[0]),
+        leaf(&[0x36, 0x78], &values[1]),
+    ];
+
+    let mut branch = RlpStream::new_list(17);
+    for nibble in 0..17 {
+        match nibble {
+            1 => branch.append(&H::hash(&leaves[0]).as_bytes()),
+            5 => branch.append(&H::hash(&leaves[1]).as_bytes()),
+            _ => branch.append_empty_data(),
+        };
+    }
+    let branch = branch.out().to_vec();
+    let root = H::hash(&branch);
+
+    let mut builder = PatriciaTrieBuilder::<H>::new();
+    builder.insert(&first, values[0].clone());
+    builder.insert(&second, values[1].clone());
+    assert_eq!(builder.root(), root);
+
+    let proof = [branch.clone(), leaves[0].clone()];
+    assert_eq!(
+        PatriciaTrie::<H>::verify_proof(root, &first, &proof),
+        Ok(Some(values[0].as_slice()))
+    );
+    assert_eq!(
+        PatriciaTrie::<H>::verify_proof(root, &[0x12, 0x35], &proof),
+        Ok(None)
+    );
+    // The same nodes do not prove anything under another hasher.
+    assert!(matches!(
+        PatriciaTrie::<Other>::verify_proof(root, &first, &proof),
+        Err(TrieError::HashMismatch { .. })
+    ));
+
+    builder.insert(&first, vec![0xcc; 40]);
+    assert_eq!(
+        PatriciaTrie::<H>::update_proof(root, &first, &[0xcc; 40], &proof),
+        Ok(builder.root())
+    );
+}
+
+#[test]
+fn keccak_trie() {
+    check_hand_built_trie::<Keccak, Sha3Hasher>();
+}
+
+#[test]
+fn external_hasher_trie() {
+    check_hand_built_trie::<Sha3Hasher, Keccak>();
+}
+
+#[cfg(feature = "solana")]
+#[test]
+fn sha256_trie() {
+    check_hand_built_trie::<Sha256, Keccak>();
+}
+
+#[cfg(feature = "solana")]
+#[test]
+fn blake3_trie() {
+    check_hand_built_trie::<Blake3, Keccak>();
+}
@@ -0,0 +1,214 @@
+//! Tries small enough that nodes are embedded in their parent instead of
+//! being referenced by hash, one test per shape.
+
+use std::sync::Arc;
+
+use eth_trie::{EthTrie as RefTrie, MemoryDB, Trie};
+use mpt_core::builder::EthTrieBuilder;
+use mpt_core::errors::TrieError;
+use mpt_core::hasher::{Keccak, TrieHasher};
+use mpt_core::node::Node;
+use mpt_core::trie::{EthTrie, ProofOutcome};
+use mpt_core::H256;
+use rlp::{Rlp, RlpStream};
+
+/// Builds the trie with `eth_trie` and the builder, checks both agree on the
+/// root, and verifies every entry and each `absent` key with both the
+/// lenient and strict verifiers. Returns the root and the root node.
+fn check_trie(entries: &[(&[u8], &[u8])], absent: &[&[u8]]) -> (H256, Vec<u8>) {
+    let mut reference = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    let mut builder = EthTrieBuilder::new();
+    for (key, value) in entries {
+        reference.insert(key, value).unwrap();
+        builder.insert(key, value.to_vec());
+    }
+    let root = H256::from(reference.root_hash().unwrap().to_fixed_bytes());
+    assert_eq!(builder.root(), root);
+
+    let present = entries.iter().map(|(key, value)| (*key, Some(*value)));
+    let absent = absent.iter().map(|key| (*key, None));
+    for (key, value) in present.chain(absent) {
+        let proof = reference.get_proof(key).unwrap();
+        assert_eq!(EthTrie::verify_proof(root, key, &proof).unwrap(), value);
+        let strict = EthTrie::verify_proof_strict(root, key, &proof).unwrap();
+        assert_eq!(strict.into_value(), value);
+    }
+
+    let root_node = reference.get_proof(entries[0].0).unwrap().remove(0);
+    assert_eq!(Keccak::hash(&root_node), root);
+    (root, root_node)
+}
+
+fn is_embedded(raw: &[u8]) -> bool {
+    Rlp::new(raw).is_list() && raw.len() < 32
+}
+
+#[test]
+fn root_under_32_bytes() {
+    let (_, root_node) = check_trie(&[(b"\x01", b"\x02")], &[b"\x03", b"\x01\x00"]);
+    assert!(root_node.len() < 32);
+    assert!(matches!(Node::decode(&root_node), Ok(Node::Leaf(_))));
+}
+
+#[test]
+fn branch_with_embedded_leaves() {
+    let (_, root_node) = check_trie(
+        &[(b"\x10", b"a"), (b"\x20", b"b"), (b"\x30", b"c")],
+        &[b"\x40", b"\x11"],
+    );
+    let Ok(Node::Branch(branch)) = Node::decode(&root_node) else {
+        panic!("root is not a branch");
+    };
+    for nibble in 1..4 {
+        assert!(is_embedded(branch.child_raw(nibble).unwrap()));
+    }
+}
+
+#[test]
+fn extension_to_embedded_branch() {
+    let (_, root_node) = check_trie(
+        &[(b"\x12\x34", b"a"), (b"\x12\x35", b"b")],
+        &[b"\x12\x36", b"\x13", b"\x12"],
+    );
+    let Ok(Node::Extension(extension)) = Node::decode(&root_node) else {
+        panic!("root is not an extension");
+    };
+    assert!(is_embedded(extension.node));
+}
+
+#[test]
+fn embedded_branch_with_value() {
+    let (_, root_node) = check_trie(
+        &[(b"\x12", b"a"), (b"\x12\x34", b"b")],
+        &[b"\x12\x30", b"\x1234", b"\x11"],
+    );
+    let Ok(Node::Extension(extension)) = Node::decode(&root_node) else {
+        panic!("root is not an extension");
+    };
+    let Ok(Node::Branch(branch)) = extension.child() else {
+        panic!("extension target is not a branch");
+    };
+    assert_eq!(branch.value().unwrap(), Some(&b"a"[..]));
+}
+
+#[test]
+fn embedded_and_hashed_siblings() {
+    let long = [7u8; 40];
+    let (_, root_node) = check_trie(&[(b"\x10", b"a"), (b"\x20", &long)], &[b"\x30"]);
+    let Ok(Node::Branch(branch)) = Node::decode(&root_node) else {
+        panic!("root is not a branch");
+    };
+    assert!(is_embedded(branch.child_raw(1).unwrap()));
+    assert!(matches!(branch.child(2), Ok(Node::Hash(_))));
+}
+
+#[test]
+fn embedded_branch_under_branch() {
+    let (_, root_node) = check_trie(
+        &[
+            (b"\x11", b"a"),
+            (b"\x12", b"b"),
+            (b"\x21", b"c"),
+            (b"\x22", b"d"),
+        ],
+        &[b"\x13", b"\x31"],
+    );
+    let Ok(Node::Branch(branch)) = Node::decode(&root_node) else {
+        panic!("root is not a branch");
+    };
+    assert!(matches!(branch.child(1), Ok(Node::Branch(_))));
+    assert!(is_embedded(branch.child_raw(1).unwrap()));
+}
+
+fn list(items: &[&[u8]]) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(items.len());
+    for item in items {
+        stream.append_raw(item, 1);
+    }
+    stream.out().to_vec()
+}
+
+fn string(data: &[u8]) -> Vec<u8> {
+    rlp::encode(&data).to_vec()
+}
+
+#[test]
+fn extension_to_leaf_rejected() {
+    // Extension over nibble 1 whose target is a leaf for nibble 2.
+    let leaf = list(&[&string(&[0x32]), &string(b"a")]);
+    let root_node = list(&[&string(&[0x11]), &leaf]);
+    let root = Keccak::hash(&root_node);
+
+    let proof = [root_node];
+    let rejected = |result| {
+        matches!(
+            result,
+            Err(TrieError::UnexpectedNode {
+                depth: 1,
+                kind: Some(_)
+            })
+        )
+    };
+    let found = EthTrie::verify_proof(root, b"\x12", &proof).map(|_| ());
+    assert!(rejected(found));
+    let updated = EthTrie::update_proof(root, b"\x12", b"b", &proof).map(|_| ());
+    assert!(rejected(updated));
+    let (keys, values) = ([b"\x12".to_vec()], [b"a".to_vec()]);
+    let range = EthTrie::verify_range_proof(root, b"\x12", b"\x12", &keys, &values, &proof);
+    assert!(rejected(range));
+}
+
+#[test]
+fn empty_extension_rejected() {
+    let branch = list(&[&[0x80][..]; 17]);
+    let root_node = list(&[&string(&[0x00]), &branch]);
+    let root = Keccak::hash(&root_node);
+
+    let proof = [root_node];
+    let result = EthTrie::verify_proof(root, b"\x12", &proof);
+    assert!(matches!(
+        result,
+        Err(TrieError::UnexpectedNode { kind: None, .. })
+    ));
+}
+
+#[test]
+fn short_child_reference_rejected() {
+    // A child reference is either an embedded list or a 32-byte hash.
+    let mut children = vec![vec![0x80]; 17];
+    children[1] = string(&[0xaa; 31]);
+    let children: Vec<&[u8]> = children.iter().map(Vec::as_slice).collect();
+    let root_node = list(&children);
+    let root = Keccak::hash(&root_node);
+
+    let proof = [root_node];
+    let result = EthTrie::verify_proof(root, b"\x12", &proof);
+    assert!(matches!(
+        result,
+        Err(TrieError::UnexpectedNode {
+            depth: 1,
+            kind: None
+        })
+    ));
+}
+
+#[test]
+fn short_node_by_hash_not_found() {
+    // Nodes under 32 bytes are never referenced by hash, so a proof for a
+    // short non-root node cannot be used.
+    let leaf = list(&[&string(&[0x20]), &string(b"a")]);
+    let mut children = vec![vec![0x80]; 17];
+    children[1] = string(Keccak::hash(&leaf).as_bytes());
+    let children: Vec<&[u8]> = children.iter().map(Vec::as_slice).collect();
+    let root_node = list(&children);
+    let root = Keccak::hash(&root_node);
+
+    let proof = [root_node, leaf];
+    let result = EthTrie::verify_proof(root, b"\x10", &proof);
+    assert!(matches!(
+        result,
+        Err(TrieError::HashMismatch { depth: 1, .. })
+    ));
+    let outcome = EthTrie::verify_proof_outcome(root, b"\x20", &proof[..1]);
+    assert!(matches!(outcome, Ok(ProofOutcome::Absent { depth: 1, .. })));
+}
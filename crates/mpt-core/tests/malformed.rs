@@ -0,0 +1,116 @@
+//! Malformed proofs and encodings, each rejected with the error that
+//! locates the fault.
+
+use std::sync::Arc;
+
+use eth_trie::{EthTrie as RefTrie, MemoryDB, Trie};
+use mpt_core::errors::TrieError;
+use mpt_core::hasher::{Keccak, TrieHasher};
+use mpt_core::nibbles::Nibbles;
+use mpt_core::trie::EthTrie;
+use mpt_core::H256;
+
+/// A trie whose paths hold only hashed nodes: 32-byte keys with 40-byte
+/// values, so every leaf and branch encodes to 32 bytes or more.
+fn hashed_trie() -> RefTrie<MemoryDB> {
+    let mut trie = RefTrie::new(Arc::new(MemoryDB::new(true)));
+    for i in 0..300u32 {
+        let key = Keccak::hash(&i.to_be_bytes());
+        trie.insert(key.as_bytes(), &[i as u8; 40]).unwrap();
+    }
+    trie.root_hash().unwrap();
+    trie
+}
+
+/// Replaces `node` with `replacement` in `proof`, where `node` is the
+/// `index`-th node, and rewrites the hash references above it so the proof
+/// still links up. Returns the new root.
+fn replace_node(proof: &mut [Vec<u8>], index: usize, replacement: Vec<u8>) -> H256 {
+    let mut old = Keccak::hash(&proof[index]);
+    proof[index] = replacement;
+    for parent in (0..index).rev() {
+        let new = Keccak::hash(&proof[parent + 1]);
+        let node = &mut proof[parent];
+        let at = node
+            .windows(32)
+            .position(|window| window == old.as_bytes())
+            .unwrap();
+        old = Keccak::hash(node);
+        node[at..at + 32].copy_from_slice(new.as_bytes());
+    }
+    Keccak::hash(&proof[0])
+}
+
+#[test]
+fn bad_rlp_names_the_node() {
+    let mut trie = hashed_trie();
+    let key = Keccak::hash(&7u32.to_be_bytes());
+    let proof = trie.get_proof(key.as_bytes()).unwrap();
+    assert!(proof.len() >= 3);
+
+    for index in 0..proof.len() {
+        let mut corrupt = proof.clone();
+        // A list header claiming one more byte than the node holds.
+        let mut node = proof[index].clone();
+        let length_bytes = usize::from(node[0] - 0xf7);
+        node[length_bytes] += 1;
+        let root = replace_node(&mut corrupt, index, node);
+
+        let result = EthTrie::verify_proof(root, key.as_bytes(), &corrupt);
+        assert!(
+            matches!(result, Err(TrieError::BadRlp { node, .. }) if node == index),
+            "node {}: {:?}",
+            index,
+            result
+        );
+    }
+}
+
+#[test]
+fn depth_bound_counts_decoded_nodes() {
+    let mut trie = hashed_trie();
+    let root = H256::from(trie.root_hash().unwrap().to_fixed_bytes());
+    let key = Keccak::hash(&7u32.to_be_bytes());
+    let proof = trie.get_proof(key.as_bytes()).unwrap();
+    assert_eq!(proof.len(), 3);
+
+    let verifier = |max_depth| {
+        EthTrie::from_proof(root, &proof)
+            .with_max_depth(max_depth)
+            .get(key.as_bytes())
+            .map(|outcome| outcome.into_value().is_some())
+    };
+    assert_eq!(verifier(2), Err(TrieError::DepthExceeded));
+    assert_eq!(verifier(3), Ok(true));
+}
+
+#[test]
+fn malformed_compact_paths() {
+    // Even-length paths pad the flag byte with a zero nibble.
+    assert_eq!(
+        Nibbles::from_compact(&[0x01, 0x23]),
+        Err(TrieError::InvalidData)
+    );
+    assert_eq!(
+        Nibbles::from_compact(&[0x2f, 0x23]),
+        Err(TrieError::InvalidData)
+    );
+    // Flags above 3 are unknown.
+    for flag in 4..16u8 {
+        assert_eq!(
+            Nibbles::from_compact(&[flag << 4, 0x23]),
+            Err(TrieError::InvalidData)
+        );
+    }
+    // An empty path has no flag byte at all.
+    assert_eq!(Nibbles::from_compact(&[]), Err(TrieError::InvalidData));
+
+    assert_eq!(
+        Nibbles::from_compact(&[0x00, 0x23]).unwrap().as_slice(),
+        &[2, 3]
+    );
+    assert_eq!(
+        Nibbles::from_compact(&[0x31, 0x23]).unwrap().as_slice(),
+        &[1, 2, 3, 16]
+    );
+}
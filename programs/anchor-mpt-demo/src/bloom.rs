@@ -0,0 +1,162 @@
+use mpt_core::hasher::{Keccak, TrieHasher};
+use rlp::{DecoderError, Rlp};
+
+use crate::envelope::fixed_bytes;
+
+pub const BLOOM_BYTES: usize = 256;
+/// Position of `logsBloom` in an RLP block header.
+const HEADER_BLOOM_INDEX: usize = 6;
+
+/// Ethereum 2048-bit logs bloom. Each log's address and topics set three
+/// bits each, taken from the low 11 bits of the first three byte pairs of
+/// their keccak hash; bit 0 is the last bit of the last byte.
+///
+/// A bloom can only rule a log out: a missing bit proves the receipt or
+/// block has no such log, while all bits set may be a false positive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bloom(pub [u8; BLOOM_BYTES]);
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom([0; BLOOM_BYTES])
+    }
+}
+
+impl Bloom {
+    /// Reads `logsBloom` from an RLP block header without decoding the rest.
+    pub fn from_header(header: &[u8]) -> Result<Self, DecoderError> {
+        fixed_bytes(&Rlp::new(header).at(HEADER_BLOOM_INDEX)?).map(Bloom)
+    }
+
+    pub fn contains_input(&self, input: &[u8]) -> bool {
+        bits(input)
+            .iter()
+            .all(|&(byte, mask)| self.0[byte] & mask == mask)
+    }
+
+    /// Whether a log from `address` with all of `topics` may be present.
+    /// `false` proves no such log was emitted.
+    pub fn may_contain_log(&self, address: &[u8; 20], topics: &[[u8; 32]]) -> bool {
+        self.contains_input(address) && topics.iter().all(|topic| self.contains_input(topic))
+    }
+}
+
+/// Byte index and mask of the three bits `input` sets.
+fn bits(input: &[u8]) -> [(usize, u8); 3] {
+    let hash = Keccak::hash(input);
+    let hash = hash.as_bytes();
+    core::array::from_fn(|i| {
+        let bit = (usize::from(hash[2 * i]) << 8 | usize::from(hash[2 * i + 1])) & 2047;
+        (BLOOM_BYTES - 1 - bit / 8, 1 << (bit % 8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::receipt::{tests::mainnet_receipt, Log, Receipt},
+        rlp::RlpStream,
+    };
+
+    /// Bloom of `logs` computed bit by bit, as stored in their receipt.
+    fn logs_bloom(logs: &[Log]) -> Bloom {
+        let mut bloom = Bloom::default();
+        let mut accrue = |input: &[u8]| {
+            for (byte, mask) in bits(input) {
+                bloom.0[byte] |= mask;
+            }
+        };
+        for log in logs {
+            accrue(&log.address);
+            log.topics.iter().for_each(|topic| accrue(topic));
+        }
+        bloom
+    }
+
+    /// Mainnet's genesis header, with `bloom` as its `logsBloom`.
+    fn genesis_header(bloom: &[u8; BLOOM_BYTES]) -> Vec<u8> {
+        let hex = |hex: &str| hex::decode(hex).unwrap();
+        let mut stream = RlpStream::new_list(15);
+        stream
+            .append(&[0u8; 32].as_slice())
+            .append(&hex(
+                "1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347",
+            ))
+            .append(&[0u8; 20].as_slice())
+            .append(&hex(
+                "d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544",
+            ))
+            .append(&hex(
+                "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            ))
+            .append(&hex(
+                "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            ))
+            .append(&bloom.as_slice())
+            .append(&0x04_0000_0000u64)
+            .append(&0u64)
+            .append(&5000u64)
+            .append(&0u64)
+            .append(&0u64)
+            .append(&hex(
+                "11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa",
+            ))
+            .append(&[0u8; 32].as_slice())
+            .append(&hex("0000000000000042"));
+        stream.out().to_vec()
+    }
+
+    #[test]
+    fn receipt_bloom_matches_its_logs() {
+        let value = mainnet_receipt();
+        let receipt = Receipt::decode(&value).unwrap();
+        let bloom = logs_bloom(&receipt.logs);
+        assert_eq!(bloom, Bloom(receipt.logs_bloom));
+        assert_eq!(Receipt::decode_bloom(&value), Ok(bloom));
+    }
+
+    #[test]
+    fn bloom_rules_out_missing_logs() {
+        let receipt = Receipt::decode(&mainnet_receipt()).unwrap();
+        let bloom = Bloom(receipt.logs_bloom);
+        for log in &receipt.logs {
+            assert!(bloom.may_contain_log(&log.address, &log.topics));
+            assert!(bloom.may_contain_log(&log.address, &[]));
+        }
+
+        let log = &receipt.logs[0];
+        assert!(!bloom.may_contain_log(&[0x42; 20], &log.topics));
+        assert!(!bloom.may_contain_log(&log.address, &[[0x42; 32]]));
+        assert!(!Bloom::default().may_contain_log(&log.address, &[]));
+    }
+
+    #[test]
+    fn header_bloom() {
+        let genesis = genesis_header(&[0; BLOOM_BYTES]);
+        assert_eq!(
+            hex::encode(Keccak::hash(&genesis)),
+            "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+        );
+        assert_eq!(Bloom::from_header(&genesis), Ok(Bloom::default()));
+
+        let receipt = Receipt::decode(&mainnet_receipt()).unwrap();
+        let header = genesis_header(&receipt.logs_bloom);
+        assert_eq!(Bloom::from_header(&header), Ok(Bloom(receipt.logs_bloom)));
+    }
+
+    #[test]
+    fn decode_bloom_needs_four_items() {
+        let value = mainnet_receipt();
+        let rlp = Rlp::new(&value[1..]);
+        let mut stream = RlpStream::new_list(3);
+        for item in rlp.iter().take(3) {
+            stream.append_raw(item.as_raw(), 1);
+        }
+        let truncated = [&[0x02][..], &stream.out()].concat();
+        assert_eq!(
+            Receipt::decode_bloom(&truncated),
+            Err(DecoderError::RlpIncorrectListLen)
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use rlp::{DecoderError, Rlp};
+
+/// EIP-2718 transaction type, shared by transactions and their receipts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxType {
+    Legacy,
+    AccessList,
+    DynamicFee,
+    Blob,
+    SetCode,
+}
+
+impl TxType {
+    pub fn from_byte(byte: u8) -> Result<Self, DecoderError> {
+        match byte {
+            0x01 => Ok(TxType::AccessList),
+            0x02 => Ok(TxType::DynamicFee),
+            0x03 => Ok(TxType::Blob),
+            0x04 => Ok(TxType::SetCode),
+            _ => Err(DecoderError::Custom("unknown transaction type")),
+        }
+    }
+}
+
+/// Splits a trie value into its type and RLP payload. Legacy values are a
+/// bare RLP list; typed values are `type || rlp(payload)`.
+pub fn split_envelope(data: &[u8]) -> Result<(TxType, &[u8]), DecoderError> {
+    match data.first() {
+        None => Err(DecoderError::RlpIsTooShort),
+        Some(&byte) if byte >= 0xc0 => Ok((TxType::Legacy, data)),
+        Some(&byte) => Ok((TxType::from_byte(byte)?, &data[1..])),
+    }
+}
+
+pub(crate) fn fixed_bytes<const N: usize>(rlp: &Rlp) -> Result<[u8; N], DecoderError> {
+    rlp.data()?
+        .try_into()
+        .map_err(|_| DecoderError::RlpInvalidLength)
+}
@@ -1,15 +1,30 @@
-use {anchor_lang::prelude::*, primitive_types_solana::H256, rlp::Rlp};
+use {
+    anchor_lang::{prelude::*, solana_program::program::set_return_data, Discriminator},
+    bloom::Bloom,
+    mpt_core::{
+        builder::EthTrieBuilder,
+        errors::TrieError,
+        hasher::{Keccak, TrieHasher},
+        proof::CompactProof,
+        trie::*,
+        H256,
+    },
+    receipt::Receipt,
+    transaction::Transaction,
+};
 
-mod errors;
-mod nibbles;
-mod node;
-mod trie;
-
-use trie::*;
+mod bloom;
+mod envelope;
+mod receipt;
+mod transaction;
 
 declare_id!("9n2uAscxSNrotCE2PC1DpKgtUFu7iSWEiJpbN9ynqzHp");
 
 pub const PROOF_SEED: &[u8] = b"MptProof";
+/// Seed of the accounts holding a trie's values for `validate_receipts_root`,
+/// kept apart from the proof accounts of the same root.
+pub const VALUES_SEED: &[u8] = b"MptValues";
+pub const ACCOUNT_SEED: &[u8] = b"EthAccount";
 
 #[program]
 pub mod anchor_mpt_demo {
@@ -28,17 +43,27 @@ pub mod anchor_mpt_demo {
         data: Vec<Vec<u8>>,
         is_merge: bool,
     ) -> Result<()> {
-        for (k, v) in data.into_iter().enumerate() {
-            if k == 0 && is_merge {
-                let len = ctx.accounts.mpt_proof.data.len();
-                ctx.accounts.mpt_proof.data[len - 1].extend_from_slice(&v);
-            } else {
-                ctx.accounts.mpt_proof.data.push(v);
-            }
-        }
+        ctx.accounts.mpt_proof.append(data, is_merge)
+    }
+
+    /// Creates the account holding the values of the trie with root
+    /// `params.hash_root`, for `validate_receipts_root`.
+    pub fn init_values(ctx: Context<InitValues>, params: InitProofParams) -> Result<()> {
+        ctx.accounts.mpt_values.data = Vec::new();
+        ctx.accounts.mpt_values.bump = ctx.bumps.mpt_values;
+        ctx.accounts.mpt_values.hash_root = params.hash_root;
+
         Ok(())
     }
 
+    pub fn append_values(
+        ctx: Context<AppendValues>,
+        data: Vec<Vec<u8>>,
+        is_merge: bool,
+    ) -> Result<()> {
+        ctx.accounts.mpt_values.append(data, is_merge)
+    }
+
     pub fn view_proof(ctx: Context<ValidateMpt>) -> Result<()> {
         let proof = vec![
             vec![
@@ -183,41 +208,522 @@ pub mod anchor_mpt_demo {
             ],
         ];
 
-        let mpt_account = &ctx.accounts.mpt_proof;
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
         msg!(
             "len: {} eq: {}  ",
-            &mpt_account.data.len(),
-            &mpt_account.data.eq(&proof)
+            &mpt_account.items.len(),
+            &mpt_account.items.iter().eq(&proof)
         );
 
         Ok(())
     }
 
     pub fn validate_mpt(ctx: Context<ValidateMpt>, key: Vec<u8>) -> Result<()> {
-        let mpt_account = &ctx.accounts.mpt_proof;
-        let root_hash = H256::from_slice(mpt_account.hash_root.as_slice());
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let value = EthTrie::verify_proof_outcome(root_hash, key.as_slice(), &nodes)
+            .and_then(ProofOutcome::require_present)
+            .map_err(trie_error)?;
+
+        log_receipt(value)
+    }
+
+    /// Checks the bloom of the receipt at `key` for a log from `address` with
+    /// all of `topics`, without decoding its logs. `false` proves the receipt
+    /// has no such log; `true` may be a false positive.
+    pub fn check_receipt_bloom(
+        ctx: Context<ValidateMpt>,
+        key: Vec<u8>,
+        address: [u8; 20],
+        topics: Vec<[u8; 32]>,
+    ) -> Result<bool> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let value = EthTrie::verify_proof_outcome(root_hash, key.as_slice(), &nodes)
+            .and_then(ProofOutcome::require_present)
+            .map_err(trie_error)?;
+        let bloom = Receipt::decode_bloom(value).map_err(|_| MptError::InvalidReceipt)?;
+        let may_contain = bloom.may_contain_log(&address, &topics);
+        msg!("bloom may contain log: {}", may_contain);
+
+        Ok(may_contain)
+    }
+
+    /// Checks the `logsBloom` of `header`, an RLP block header hashing to
+    /// `block_hash`, for a log from `address` with all of `topics`. `false`
+    /// proves no transaction in the block emitted such a log.
+    pub fn check_header_bloom(
+        _ctx: Context<CheckHeaderBloom>,
+        block_hash: [u8; 32],
+        header: Vec<u8>,
+        address: [u8; 20],
+        topics: Vec<[u8; 32]>,
+    ) -> Result<bool> {
+        require!(
+            Keccak::hash(&header).as_bytes() == block_hash.as_slice(),
+            MptError::BlockHashMismatch
+        );
+        let bloom = Bloom::from_header(&header).map_err(|_| MptError::InvalidHeader)?;
+        let may_contain = bloom.may_contain_log(&address, &topics);
+        msg!("bloom may contain log: {}", may_contain);
+
+        Ok(may_contain)
+    }
+
+    pub fn validate_mpt_by_index(ctx: Context<ValidateMpt>, index: u64) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let key = index_key(index);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let value = EthTrie::verify_proof_outcome(root_hash, &key, &nodes)
+            .and_then(ProofOutcome::require_present)
+            .map_err(trie_error)?;
+
+        log_receipt(value)
+    }
+
+    pub fn validate_mpt_strict(ctx: Context<ValidateMpt>, key: Vec<u8>) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let value = EthTrie::verify_proof_strict(root_hash, key.as_slice(), &nodes)
+            .and_then(ProofOutcome::require_present)
+            .map_err(trie_error)?;
+
+        log_receipt(value)
+    }
+
+    pub fn validate_transaction(
+        ctx: Context<ValidateMpt>,
+        key: Vec<u8>,
+    ) -> Result<TransactionInfo> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let value = EthTrie::verify_proof_outcome(root_hash, key.as_slice(), &nodes)
+            .and_then(ProofOutcome::require_present)
+            .map_err(trie_error)?;
+
+        transaction_info(value)
+    }
+
+    pub fn validate_transaction_by_index(
+        ctx: Context<ValidateMpt>,
+        index: u64,
+    ) -> Result<TransactionInfo> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let key = index_key(index);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let value = EthTrie::verify_proof_outcome(root_hash, &key, &nodes)
+            .and_then(ProofOutcome::require_present)
+            .map_err(trie_error)?;
+
+        transaction_info(value)
+    }
+
+    /// Checks that the values uploaded to `mpt_values`, taken as a block's
+    /// receipts (or transactions) in order, hash to its root.
+    pub fn validate_receipts_root(ctx: Context<ValidateValues>) -> Result<()> {
+        let data = ctx.accounts.mpt_values.try_borrow_data()?;
+        let mpt_account = ProofView::load_seeded(&ctx.accounts.mpt_values, &data, VALUES_SEED)?;
+
+        let root = EthTrieBuilder::ordered_root(&mpt_account.items);
+        require!(
+            root.as_bytes() == mpt_account.hash_root.as_slice(),
+            MptError::RootMismatch
+        );
+        msg!("root matches {} values", mpt_account.items.len());
+
+        Ok(())
+    }
+
+    /// Walks the proof for `key` and returns the nodes visited, whether or not
+    /// verification succeeds, so failed relays can be debugged.
+    pub fn trace_proof(ctx: Context<ValidateMpt>, key: Vec<u8>) -> Result<ProofTraceInfo> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let (trace, result) = EthTrie::verify_proof_traced(root_hash, key.as_slice(), &nodes);
+        Ok(ProofTraceInfo::new(trace, result))
+    }
+
+    pub fn validate_absence(ctx: Context<ValidateMpt>, key: Vec<u8>) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let outcome =
+            EthTrie::verify_proof_outcome(root_hash, key.as_slice(), &nodes).map_err(trie_error)?;
+        match outcome {
+            ProofOutcome::Absent {
+                divergence_node,
+                depth,
+            } => {
+                msg!("absent: node {:?} depth {}", divergence_node, depth);
+                Ok(())
+            }
+            ProofOutcome::Present(_) => err!(MptError::KeyPresent),
+        }
+    }
+
+    pub fn validate_secure(ctx: Context<ValidateMpt>, key: Vec<u8>) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let outcome =
+            EthTrie::verify_secure_proof(root_hash, key.as_slice(), &nodes).map_err(trie_error)?;
+        match outcome {
+            ProofOutcome::Present(value) => msg!("value: {:?}", value),
+            ProofOutcome::Absent { depth, .. } => msg!("absent at depth {}", depth),
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_account(
+        ctx: Context<ValidateAccount>,
+        state_root: [u8; 32],
+        address: [u8; 20],
+    ) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        require!(state_root == mpt_account.hash_root, MptError::RootMismatch);
+        let nodes = mpt_account.nodes(&address)?;
+
+        let account = EthTrie::verify_account_proof(H256(state_root), &address, &nodes)
+            .map_err(trie_error)?
+            .ok_or(MptError::KeyNotFound)?;
+
+        let account_state = &mut ctx.accounts.account_state;
+        account_state.state_root = state_root;
+        account_state.address = address;
+        account_state.nonce = account.nonce;
+        account.balance.to_big_endian(&mut account_state.balance);
+        account_state.storage_root = account.storage_root.to_fixed_bytes();
+        account_state.code_hash = account.code_hash.to_fixed_bytes();
+        account_state.bump = ctx.bumps.account_state;
+
+        Ok(())
+    }
 
-        let mut rlp_signed_tx =
-            EthTrie::verify_proof(root_hash, key.as_slice(), mpt_account.data.clone())
-                .unwrap()
-                .unwrap();
-        rlp_signed_tx.remove(0);
+    pub fn validate_storage(
+        ctx: Context<ValidateStorage>,
+        address: [u8; 20],
+        slot: [u8; 32],
+    ) -> Result<()> {
+        let account_data = ctx.accounts.account_proof.try_borrow_data()?;
+        let storage_data = ctx.accounts.storage_proof.try_borrow_data()?;
+        let account_proof = ProofView::load(&ctx.accounts.account_proof, &account_data)?;
+        let storage_proof = ProofView::load(&ctx.accounts.storage_proof, &storage_data)?;
+        let state_root = H256(account_proof.hash_root);
+
+        let word = EthTrie::verify_storage_proof(
+            state_root,
+            &address,
+            &account_proof.nodes(&address)?,
+            &slot,
+            &storage_proof.nodes(&slot)?,
+        )
+        .map_err(trie_error)?;
+        msg!("slot value: {:?}", word);
+        set_return_data(word.as_bytes());
+
+        Ok(())
+    }
+
+    /// Verifies every key in `keys` against the proof and returns, for each,
+    /// the keccak of its value, or `None` if the key is absent.
+    pub fn validate_multi(
+        ctx: Context<ValidateMpt>,
+        keys: Vec<Vec<u8>>,
+    ) -> Result<Vec<Option<[u8; 32]>>> {
+        require!(keys.len() <= MAX_MULTI_KEYS, MptError::TooManyKeys);
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&[])?;
+
+        let outcomes =
+            EthTrie::verify_multiproof(root_hash, keys.as_slice(), &nodes).map_err(trie_error)?;
+        for (key, outcome) in keys.iter().zip(outcomes.iter()) {
+            match outcome {
+                ProofOutcome::Present(value) => {
+                    msg!("key {:?}: present, {} bytes", key, value.len())
+                }
+                ProofOutcome::Absent { depth, .. } => {
+                    msg!("key {:?}: absent at depth {}", key, depth)
+                }
+            }
+        }
+
+        Ok(outcomes
+            .into_iter()
+            .map(|outcome| {
+                let value = outcome.into_value()?;
+                Some(Keccak::hash(value).to_fixed_bytes())
+            })
+            .collect())
+    }
+
+    /// Checks that `keys` and `values` are all the entries with a key in
+    /// `[first, last]`, given edge proofs for both bounds in `mpt_proof`.
+    pub fn validate_range(
+        ctx: Context<ValidateMpt>,
+        first: Vec<u8>,
+        last: Vec<u8>,
+        keys: Vec<Vec<u8>>,
+        values: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&[])?;
 
-        let rlp = Rlp::new(&rlp_signed_tx);
-        msg!("rlp: {:?}", rlp.at(0).unwrap().as_val::<u8>()); //  交易是否成功的状态  u8
-        msg!("rlp: {:?}", rlp.at(1).unwrap().as_val::<u64>()); //  effectiveGasPrice: u64
+        EthTrie::verify_range_proof(root_hash, &first, &last, &keys, &values, &nodes)
+            .map_err(trie_error)?;
+        msg!("range holds {} entries", keys.len());
 
         Ok(())
     }
+
+    /// Checks that `values` are the receipts (or transactions) at indices
+    /// `first, first + 1, ...` and that the block has no others in
+    /// `[first, last]`, given edge proofs for every span of `index_spans`.
+    pub fn validate_index_range(
+        ctx: Context<ValidateMpt>,
+        first: u64,
+        last: u64,
+        values: Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&[])?;
+
+        EthTrie::verify_index_range_proof(root_hash, first, last, &values, &nodes)
+            .map_err(trie_error)?;
+        msg!("range holds {} entries", values.len());
+
+        Ok(())
+    }
+
+    /// Checks that the leaf for `key`, holding the last `rest` nibbles of the
+    /// key, hashes to `leaf_hash`, with `mpt_proof` holding the proof without
+    /// the leaf, and returns the leaf's depth in nibbles.
+    ///
+    /// The commitment is the keccak of the whole leaf node, not of the value:
+    /// the leaf's hash cannot be derived from the value's hash, so a value
+    /// hash could not be checked against the proof. `EthTrie::leaf_hash`
+    /// opens the commitment given the value.
+    pub fn validate_leaf_hash(
+        ctx: Context<ValidateMpt>,
+        key: Vec<u8>,
+        rest: u16,
+        leaf_hash: [u8; 32],
+    ) -> Result<u16> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let depth =
+            EthTrie::verify_leaf_hash(root_hash, &key, rest.into(), H256(leaf_hash), &nodes)
+                .map_err(trie_error)?;
+        msg!("leaf at depth {}", depth);
+
+        Ok(depth as u16)
+    }
+
+    /// Returns the root after writing `value` at `key` in the trie proven by
+    /// `mpt_proof`, or after removing `key` if `value` is empty.
+    pub fn update_root(
+        ctx: Context<ValidateMpt>,
+        key: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<[u8; 32]> {
+        let data = ctx.accounts.mpt_proof.try_borrow_data()?;
+        let mpt_account = ProofView::load(&ctx.accounts.mpt_proof, &data)?;
+        let root_hash = H256(mpt_account.hash_root);
+        let nodes = mpt_account.nodes(&key)?;
+
+        let new_root =
+            EthTrie::update_proof(root_hash, &key, &value, &nodes).map_err(trie_error)?;
+        msg!("new root: {:?}", new_root);
+
+        Ok(new_root.to_fixed_bytes())
+    }
+}
+
+fn log_receipt(value: &[u8]) -> Result<()> {
+    let receipt = Receipt::decode(value).map_err(|_| MptError::InvalidReceipt)?;
+    msg!("type: {:?}", receipt.tx_type);
+    msg!("status: {:?}", receipt.status_or_root);
+    msg!("cumulativeGasUsed: {}", receipt.cumulative_gas_used);
+    msg!("logs: {}", receipt.logs.len());
+
+    Ok(())
+}
+
+fn transaction_info(value: &[u8]) -> Result<TransactionInfo> {
+    let tx = Transaction::decode(value).map_err(|_| MptError::InvalidTransaction)?;
+    let mut value = [0u8; 32];
+    tx.value.to_big_endian(&mut value);
+
+    Ok(TransactionInfo {
+        chain_id: tx.chain_id,
+        nonce: tx.nonce,
+        to: tx.to,
+        value,
+        input: tx.input,
+    })
 }
 
+/// Logs the full trie error and maps it to its program error code.
+fn trie_error(error: TrieError) -> MptError {
+    msg!("{}", error);
+    MptError::from(error)
+}
+
+/// Program errors. Clients decode these by number (6000 + position), so new
+/// variants go at the end.
+#[error_code]
+pub enum MptError {
+    #[msg("Invalid proof")]
+    InvalidProof,
+    #[msg("Key not found under root")]
+    KeyNotFound,
+    #[msg("Key present under root")]
+    KeyPresent,
+    #[msg("Invalid receipt encoding")]
+    InvalidReceipt,
+    #[msg("Invalid transaction encoding")]
+    InvalidTransaction,
+    #[msg("No proof node to merge into")]
+    NothingToMerge,
+    #[msg("Proof exceeds the maximum trie depth")]
+    DepthExceeded,
+    #[msg("Proof node is not valid RLP")]
+    BadRlp,
+    #[msg("No proof node matches the expected hash")]
+    HashMismatch,
+    #[msg("Key path diverged from the trie")]
+    PathDiverged,
+    #[msg("Unexpected node kind in proof")]
+    UnexpectedNode,
+    #[msg("Proof contains a node not on the key's path")]
+    UnusedNode,
+    #[msg("Proof contains a duplicate node")]
+    DuplicateNode,
+    #[msg("Proof nodes are not in path order")]
+    OutOfOrderNode,
+    #[msg("Proof contains a node that should be embedded in its parent")]
+    NonCanonicalInlineNode,
+    #[msg("Computed trie root does not match")]
+    RootMismatch,
+    #[msg("Range entries are unsorted or outside the range")]
+    InvalidRange,
+    #[msg("Range entries do not match the trie")]
+    RangeMismatch,
+    #[msg("Compact proof is malformed")]
+    BadProofEncoding,
+    #[msg("Leaf does not match the committed hash")]
+    LeafHashMismatch,
+    #[msg("Invalid block header encoding")]
+    InvalidHeader,
+    #[msg("Block header does not hash to the block hash")]
+    BlockHashMismatch,
+    #[msg("Compact proof is for another key")]
+    ProofKeyMismatch,
+    #[msg("Too many keys for one multiproof")]
+    TooManyKeys,
+}
+
+impl From<TrieError> for MptError {
+    fn from(error: TrieError) -> Self {
+        match error {
+            TrieError::Decoder(_) | TrieError::BadRlp { .. } => MptError::BadRlp,
+            TrieError::InvalidData | TrieError::UnexpectedNode { .. } => MptError::UnexpectedNode,
+            TrieError::InvalidProof => MptError::InvalidProof,
+            TrieError::HashMismatch { .. } => MptError::HashMismatch,
+            TrieError::PathDiverged { .. } => MptError::PathDiverged,
+            TrieError::DepthExceeded => MptError::DepthExceeded,
+            TrieError::UnusedNode(_) => MptError::UnusedNode,
+            TrieError::DuplicateNode(_) => MptError::DuplicateNode,
+            TrieError::OutOfOrderNode(_) => MptError::OutOfOrderNode,
+            TrieError::NonCanonicalInlineNode => MptError::NonCanonicalInlineNode,
+            TrieError::InvalidRange => MptError::InvalidRange,
+            TrieError::RangeMismatch { .. } => MptError::RangeMismatch,
+            TrieError::BadProofEncoding => MptError::BadProofEncoding,
+            TrieError::LeafHashMismatch { .. } => MptError::LeafHashMismatch,
+        }
+    }
+}
+
+/// Reads an `MptProof` in place. Its owner and address are checked by
+/// `ProofView::load`, so the proof is not deserialized into owned vectors.
 #[derive(Accounts)]
 pub struct ValidateMpt<'info> {
+    /// CHECK: checked by `ProofView::load`.
+    pub mpt_proof: UncheckedAccount<'info>,
+}
+
+/// `check_header_bloom` reads only its arguments.
+#[derive(Accounts)]
+pub struct CheckHeaderBloom {}
+
+/// Reads the values of a trie in place, like `ValidateMpt`.
+#[derive(Accounts)]
+pub struct ValidateValues<'info> {
+    /// CHECK: checked by `ProofView::load_seeded`.
+    pub mpt_values: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(state_root: [u8; 32], address: [u8; 20])]
+pub struct ValidateAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: checked by `ProofView::load`, and its root against
+    /// `state_root` by `validate_account`.
+    pub mpt_proof: UncheckedAccount<'info>,
     #[account(
-        seeds = [PROOF_SEED, mpt_proof.hash_root.as_ref()],
-        bump = mpt_proof.bump
+        init,
+        payer = payer,
+        space = 8 + 32 + 20 + 8 + 32 + 32 + 32 + 1,
+        seeds = [ACCOUNT_SEED, state_root.as_ref(), address.as_ref()],
+        bump
     )]
-    pub mpt_proof: Account<'info, MptProof>,
+    pub account_state: Account<'info, AccountState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateStorage<'info> {
+    /// CHECK: checked by `ProofView::load`.
+    pub account_proof: UncheckedAccount<'info>,
+    /// CHECK: checked by `ProofView::load`.
+    pub storage_proof: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
@@ -246,6 +752,34 @@ pub struct AppendProof<'info> {
     pub mpt_proof: Account<'info, MptProof>,
 }
 
+#[derive(Accounts)]
+#[instruction(params: InitProofParams)]
+pub struct InitValues<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + 8 + 32 + params.proof_size as usize,
+        seeds = [VALUES_SEED, params.hash_root.as_ref()],
+        bump
+    )]
+    pub mpt_values: Account<'info, MptProof>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AppendValues<'info> {
+    #[account(
+        mut,
+        seeds = [VALUES_SEED, mpt_values.hash_root.as_ref()],
+        bump = mpt_values.bump
+    )]
+    pub mpt_values: Account<'info, MptProof>,
+}
+
+/// Items uploaded for a trie: the nodes of a proof, or the trie's values when
+/// held at a `VALUES_SEED` address.
 #[account]
 #[derive(Debug)]
 pub struct MptProof {
@@ -254,6 +788,176 @@ pub struct MptProof {
     bump: u8,
 }
 
+/// An `MptProof` read in place: `items` borrow the account data instead of
+/// being copied out of it.
+struct ProofView<'a> {
+    items: Vec<&'a [u8]>,
+    hash_root: [u8; 32],
+}
+
+impl MptProof {
+    /// Appends `items`, the first of which continues the last item held if
+    /// `is_merge` is set, so items too large for one transaction can be
+    /// uploaded in parts.
+    fn append(&mut self, items: Vec<Vec<u8>>, is_merge: bool) -> Result<()> {
+        for (k, v) in items.into_iter().enumerate() {
+            if k == 0 && is_merge {
+                self.data
+                    .last_mut()
+                    .ok_or(MptError::NothingToMerge)?
+                    .extend_from_slice(&v);
+            } else {
+                self.data.push(v);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ProofView<'a> {
+    /// Parses `data`, borrowed from `info`, after checking that `info` is an
+    /// `MptProof` of this program at the address derived from its root.
+    fn load(info: &AccountInfo, data: &'a [u8]) -> Result<Self> {
+        Self::load_seeded(info, data, PROOF_SEED)
+    }
+
+    /// Like `load`, for an account derived from `seed` and its root.
+    fn load_seeded(info: &AccountInfo, data: &'a [u8], seed: &[u8]) -> Result<Self> {
+        require_keys_eq!(*info.owner, ID, ErrorCode::AccountOwnedByWrongProgram);
+        let mut rest = data;
+        require!(
+            take(&mut rest, 8)? == MptProof::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        // Borsh layout of `MptProof`: the items, then the root and the bump.
+        let count = take_len(&mut rest)?;
+        let mut items = Vec::new();
+        for _ in 0..count {
+            let len = take_len(&mut rest)?;
+            items.push(take(&mut rest, len)?);
+        }
+        let hash_root: [u8; 32] = take(&mut rest, 32)?.try_into().unwrap();
+        let bump = take(&mut rest, 1)?;
+
+        let address = Pubkey::create_program_address(&[seed, &hash_root, bump], &ID)
+            .map_err(|_| ErrorCode::ConstraintSeeds)?;
+        require_keys_eq!(address, *info.key, ErrorCode::ConstraintSeeds);
+        Ok(ProofView { items, hash_root })
+    }
+
+    /// Proof nodes held by the account for verifying `key`, empty when
+    /// verifying several keys: the uploaded items, or the nodes of a compact
+    /// proof uploaded as a single item, which must be for the same root and
+    /// key.
+    fn nodes(&self, key: &[u8]) -> Result<Vec<&'a [u8]>> {
+        match self.items.as_slice() {
+            [item] if CompactProof::is_compact(item) => {
+                let proof = CompactProof::decode(item).map_err(trie_error)?;
+                require!(
+                    proof.root.as_bytes() == self.hash_root.as_slice(),
+                    MptError::RootMismatch
+                );
+                require!(proof.key == key, MptError::ProofKeyMismatch);
+                Ok(proof.nodes)
+            }
+            items => Ok(items.to_vec()),
+        }
+    }
+}
+
+/// Splits the first `len` bytes off `data`.
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    require!(data.len() >= len, ErrorCode::AccountDidNotDeserialize);
+    let (head, rest) = data.split_at(len);
+    *data = rest;
+    Ok(head)
+}
+
+/// Splits a borsh `u32` length off `data`.
+fn take_len(data: &mut &[u8]) -> Result<usize> {
+    let len = take(data, 4)?.try_into().unwrap();
+    Ok(u32::from_le_bytes(len) as usize)
+}
+
+/// Ethereum account state proven against `state_root`. `balance` is a
+/// big-endian U256.
+#[account]
+#[derive(Debug)]
+pub struct AccountState {
+    pub state_root: [u8; 32],
+    pub address: [u8; 20],
+    pub nonce: u64,
+    pub balance: [u8; 32],
+    pub storage_root: [u8; 32],
+    pub code_hash: [u8; 32],
+    pub bump: u8,
+}
+
+/// Transaction fields returned by `validate_transaction`. `value` is a
+/// big-endian U256.
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct TransactionInfo {
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub to: Option<[u8; 20]>,
+    pub value: [u8; 32],
+    pub input: Vec<u8>,
+}
+
+/// Keys checked by one `validate_multi`, so a value hash for each fits in the
+/// 1024 bytes of return data.
+pub const MAX_MULTI_KEYS: usize = 30;
+
+/// Steps kept by `trace_proof`, so the serialized trace fits in the 1024
+/// bytes of return data with every step hashed.
+pub const MAX_TRACE_STEPS: usize = 28;
+
+/// Result of `trace_proof`. `steps` holds the last `MAX_TRACE_STEPS` nodes
+/// visited, nearest the failure, and `skipped` counts the ones dropped before
+/// them. `error` is the `MptError` code verification failed with, if any.
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct ProofTraceInfo {
+    pub steps: Vec<TraceStepInfo>,
+    pub skipped: u16,
+    pub present: bool,
+    pub error: Option<u32>,
+}
+
+impl ProofTraceInfo {
+    fn new(trace: Vec<TraceStep>, result: TrieResult<ProofOutcome>) -> Self {
+        let skipped = trace.len().saturating_sub(MAX_TRACE_STEPS);
+        let steps = trace
+            .into_iter()
+            .skip(skipped)
+            .map(|step| TraceStepInfo {
+                hash: step.hash.map(|hash| hash.to_fixed_bytes()),
+                kind: step.kind as u8,
+                nibbles: step.nibbles as u16,
+            })
+            .collect();
+        let (present, error) = match result {
+            Ok(outcome) => (outcome.into_value().is_some(), None),
+            Err(error) => (false, Some(trie_error(error).into())),
+        };
+
+        ProofTraceInfo {
+            steps,
+            skipped: skipped as u16,
+            present,
+            error,
+        }
+    }
+}
+
+/// A visited node. `kind` is 0 empty, 1 leaf, 2 extension, 3 branch;
+/// `hash` is `None` for embedded nodes.
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct TraceStepInfo {
+    pub hash: Option<[u8; 32]>,
+    pub kind: u8,
+    pub nibbles: u16,
+}
+
 #[derive(Clone, AnchorSerialize, AnchorDeserialize)]
 pub struct InitProofParams {
     pub hash_root: [u8; 32],
@@ -261,43 +965,117 @@ pub struct InitProofParams {
     // pub data: Vec<Vec<u8>>,
 }
 
-// #[derive(Debug)]
-// pub struct LogInfo {
-//     address: [u8; 20],
-//     topics: Vec<[u8; 32]>,
-//     data: Vec<u8>,
-// }
-
-// impl Decodable for LogInfo {
-//     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
-//         let address = rlp.at(0).unwrap().data().unwrap().to_owned();
-//         let address: [u8; 20] = match address.try_into() {
-//             Ok(arr) => arr,
-//             Err(e) => {
-//                 // return Err("Failed to convert: {:?}", e);
-//                 return Err(DecoderError::RlpExpectedToBeData);
-//             }
-//         };
-
-//         let mut topic_list = Vec::new();
-//         let topics = rlp.at(1).unwrap();
-//         for topic in topics.iter() {
-//             let value = topic.data().unwrap();
-//             let t: [u8; 32] = match value.try_into() {
-//                 Ok(arr) => arr,
-//                 Err(e) => {
-//                     // return Err("Failed to convert: {:?}", e);
-//                     return Err(DecoderError::RlpExpectedToBeData);
-//                 }
-//             };
-//             topic_list.push(t);
-//         }
-//         let data = rlp.at(2).unwrap().data().unwrap().to_owned();
-
-//         Ok(LogInfo {
-//             address,
-//             topics: topic_list,
-//             data,
-//         })
-//     }
-// }
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, anchor_lang::solana_program::program::MAX_RETURN_DATA, mpt_core::node::NodeKind,
+    };
+
+    /// Loads `data` as the proof account `key`, owned by `owner`, and
+    /// returns its items and root.
+    fn load_view(key: &Pubkey, owner: &Pubkey, data: &[u8]) -> Result<(Vec<Vec<u8>>, [u8; 32])> {
+        let (mut lamports, mut account_data) = (0, data.to_vec());
+        let info = AccountInfo::new(
+            key,
+            false,
+            false,
+            &mut lamports,
+            &mut account_data,
+            owner,
+            false,
+            0,
+        );
+        let view = ProofView::load(&info, data)?;
+        let items = view.items.iter().map(|item| item.to_vec()).collect();
+        Ok((items, view.hash_root))
+    }
+
+    #[test]
+    fn proof_view_reads_account_data() {
+        let hash_root = [7u8; 32];
+        let (address, bump) = Pubkey::find_program_address(&[PROOF_SEED, &hash_root], &ID);
+        let proof = MptProof {
+            data: vec![vec![1, 2, 3], vec![], vec![4; 40]],
+            hash_root,
+            bump,
+        };
+        let mut data = Vec::new();
+        proof.try_serialize(&mut data).unwrap();
+        // Space left over by `init_proof` follows the serialized proof.
+        data.resize(data.len() + 16, 0);
+
+        assert_eq!(
+            load_view(&address, &ID, &data).unwrap(),
+            (proof.data, hash_root)
+        );
+        let other = Pubkey::new_unique();
+        assert!(load_view(&other, &ID, &data).is_err());
+        assert!(load_view(&address, &other, &data).is_err());
+        assert!(load_view(&address, &ID, &data[..20]).is_err());
+    }
+
+    #[test]
+    fn append_merges_into_the_last_item() {
+        let mut proof = MptProof {
+            data: Vec::new(),
+            hash_root: [0; 32],
+            bump: 0,
+        };
+        assert_eq!(
+            proof.append(vec![vec![1]], true),
+            Err(MptError::NothingToMerge.into())
+        );
+        assert!(proof.data.is_empty());
+
+        proof.append(vec![vec![1, 2], vec![3]], false).unwrap();
+        proof.append(vec![vec![4], vec![5]], true).unwrap();
+        assert_eq!(proof.data, [vec![1, 2], vec![3, 4], vec![5]]);
+    }
+
+    #[test]
+    fn compact_proof_is_checked_against_the_key() {
+        let root = H256::repeat_byte(7);
+        let encoded = CompactProof::encode(root, &[0x80], &[[0xc0]]).unwrap();
+        let view = ProofView {
+            items: vec![&encoded],
+            hash_root: root.to_fixed_bytes(),
+        };
+        assert_eq!(view.nodes(&[0x80]).unwrap(), [&[0xc0]]);
+        assert_eq!(view.nodes(&[0x01]), Err(MptError::ProofKeyMismatch.into()));
+        // A proof for one key is not taken for a multiproof.
+        assert_eq!(view.nodes(&[]), Err(MptError::ProofKeyMismatch.into()));
+
+        let other_root = ProofView {
+            items: vec![&encoded],
+            hash_root: [0; 32],
+        };
+        assert_eq!(
+            other_root.nodes(&[0x80]),
+            Err(MptError::RootMismatch.into())
+        );
+    }
+
+    #[test]
+    fn multiproof_results_fit_in_return_data() {
+        let results: Vec<Option<[u8; 32]>> = vec![Some([0xff; 32]); MAX_MULTI_KEYS];
+        assert!(results.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+    }
+
+    #[test]
+    fn trace_fits_in_return_data() {
+        let trace: Vec<TraceStep> = (0..65)
+            .map(|nibbles| TraceStep {
+                hash: Some(H256::repeat_byte(nibbles as u8)),
+                kind: NodeKind::Branch,
+                nibbles,
+            })
+            .collect();
+        let info = ProofTraceInfo::new(trace, Err(TrieError::DepthExceeded));
+
+        assert_eq!(info.steps.len(), MAX_TRACE_STEPS);
+        assert_eq!(usize::from(info.skipped), 65 - MAX_TRACE_STEPS);
+        assert_eq!(info.steps[0].nibbles, info.skipped);
+        assert_eq!(info.steps.last().unwrap().nibbles, 64);
+        assert!(info.try_to_vec().unwrap().len() <= MAX_RETURN_DATA);
+    }
+}
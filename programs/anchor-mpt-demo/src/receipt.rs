@@ -0,0 +1,198 @@
+use primitive_types_solana::H256;
+use rlp::{Decodable, DecoderError, Rlp};
+
+use crate::bloom::Bloom;
+use crate::envelope::{fixed_bytes, split_envelope, TxType};
+
+/// Receipt outcome: a state root before Byzantium, a status flag after.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatusOrRoot {
+    Status(bool),
+    Root(H256),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Log {
+    pub address: [u8; 20],
+    pub topics: Vec<[u8; 32]>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub tx_type: TxType,
+    pub status_or_root: StatusOrRoot,
+    pub cumulative_gas_used: u64,
+    pub logs_bloom: [u8; 256],
+    pub logs: Vec<Log>,
+}
+
+impl Receipt {
+    /// Decodes a receipt trie value, legacy or EIP-2718 typed.
+    pub fn decode(data: &[u8]) -> Result<Self, DecoderError> {
+        let (tx_type, payload) = split_envelope(data)?;
+        let rlp = Rlp::new(payload);
+        if rlp.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let status_or_root = {
+            let item = rlp.at(0)?.data()?;
+            match item {
+                [] => StatusOrRoot::Status(false),
+                [1] => StatusOrRoot::Status(true),
+                _ if item.len() == 32 => StatusOrRoot::Root(H256::from_slice(item)),
+                _ => return Err(DecoderError::Custom("invalid receipt status")),
+            }
+        };
+
+        Ok(Receipt {
+            tx_type,
+            status_or_root,
+            cumulative_gas_used: rlp.val_at(1)?,
+            logs_bloom: fixed_bytes(&rlp.at(2)?)?,
+            logs: rlp.list_at(3)?,
+        })
+    }
+
+    /// Reads only the `logsBloom` of a receipt trie value, leaving its logs
+    /// undecoded.
+    pub fn decode_bloom(data: &[u8]) -> Result<Bloom, DecoderError> {
+        let (_, payload) = split_envelope(data)?;
+        let rlp = Rlp::new(payload);
+        if rlp.item_count()? != 4 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+        fixed_bytes(&rlp.at(2)?).map(Bloom)
+    }
+}
+
+impl Decodable for Log {
+    fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
+        if rlp.item_count()? != 3 {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let topics_rlp = rlp.at(1)?;
+        if !topics_rlp.is_list() {
+            return Err(DecoderError::RlpExpectedToBeList);
+        }
+        let mut topics = Vec::new();
+        for topic in topics_rlp.iter() {
+            topics.push(fixed_bytes(&topic)?);
+        }
+
+        Ok(Log {
+            address: fixed_bytes(&rlp.at(0)?)?,
+            topics,
+            data: rlp.at(2)?.data()?.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {super::*, mpt_core::trie::EthTrie, rlp::RlpStream};
+
+    /// Receipt proven by the CLI's sample proof, a mainnet EIP-1559 receipt.
+    pub(crate) fn mainnet_receipt() -> Vec<u8> {
+        let params: serde_json::Value =
+            serde_json::from_str(include_str!("../../../cli/proof/mpt.json")).unwrap();
+        let hex = |value: &serde_json::Value| hex::decode(value.as_str().unwrap()).unwrap();
+        let root = H256::from_slice(&hex(&params["root"]));
+        let proof: Vec<Vec<u8>> = params["proof"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(hex)
+            .collect();
+        EthTrie::verify_proof(root, &hex(&params["key"]), &proof)
+            .unwrap()
+            .unwrap()
+            .to_vec()
+    }
+
+    /// `items` as an RLP list, after `prefix`.
+    fn list(prefix: &[u8], items: &[&[u8]]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(items.len());
+        for item in items {
+            stream.append_raw(item, 1);
+        }
+        [prefix, &stream.out()].concat()
+    }
+
+    #[test]
+    fn mainnet_dynamic_fee_receipt() {
+        let receipt = Receipt::decode(&mainnet_receipt()).unwrap();
+        assert_eq!(receipt.tx_type, TxType::DynamicFee);
+        assert_eq!(receipt.status_or_root, StatusOrRoot::Status(true));
+        assert_eq!(receipt.cumulative_gas_used, 0x0113_571a);
+        assert!(!receipt.logs.is_empty());
+        assert!(receipt.logs.iter().all(|log| log.topics.len() <= 4));
+    }
+
+    #[test]
+    fn every_receipt_envelope() {
+        // Typed receipts share the legacy payload, behind their type byte.
+        let value = mainnet_receipt();
+        let expected = Receipt::decode(&value).unwrap();
+        let payload = &value[1..];
+
+        for (prefix, tx_type) in [
+            (&[][..], TxType::Legacy),
+            (&[0x01], TxType::AccessList),
+            (&[0x02], TxType::DynamicFee),
+            (&[0x03], TxType::Blob),
+            (&[0x04], TxType::SetCode),
+        ] {
+            let receipt = Receipt::decode(&[prefix, payload].concat()).unwrap();
+            assert_eq!(receipt.tx_type, tx_type);
+            assert_eq!(receipt.status_or_root, expected.status_or_root);
+            assert_eq!(receipt.logs, expected.logs);
+        }
+    }
+
+    #[test]
+    fn status_and_state_root() {
+        let value = mainnet_receipt();
+        let rlp = Rlp::new(&value[1..]);
+        let rest: Vec<&[u8]> = rlp.iter().skip(1).map(|item| item.as_raw()).collect();
+        let receipt_with = |outcome: &[u8]| {
+            let items = [&[outcome], rest.as_slice()].concat();
+            Receipt::decode(&list(&[], &items)).map(|receipt| receipt.status_or_root)
+        };
+
+        // Pre-Byzantium receipts hold the post-transaction state root.
+        let root = rlp::encode(&[0x5a; 32].as_slice());
+        assert_eq!(
+            receipt_with(&root),
+            Ok(StatusOrRoot::Root(H256::repeat_byte(0x5a)))
+        );
+        assert_eq!(receipt_with(&[0x80]), Ok(StatusOrRoot::Status(false)));
+        assert_eq!(receipt_with(&[0x01]), Ok(StatusOrRoot::Status(true)));
+        assert!(receipt_with(&[0x02]).is_err());
+    }
+
+    #[test]
+    fn malformed_receipts() {
+        let value = mainnet_receipt();
+        let mut unknown = value.clone();
+        unknown[0] = 0x05;
+        assert_eq!(
+            Receipt::decode(&unknown),
+            Err(DecoderError::Custom("unknown transaction type"))
+        );
+
+        let rlp = Rlp::new(&value[1..]);
+        let items: Vec<&[u8]> = rlp.iter().map(|item| item.as_raw()).collect();
+        for count in [3, 5] {
+            let mut items = items.clone();
+            items.resize(count, &[0x80]);
+            assert_eq!(
+                Receipt::decode(&list(&[0x02], &items)),
+                Err(DecoderError::RlpIncorrectListLen)
+            );
+        }
+        assert_eq!(Receipt::decode(&[]), Err(DecoderError::RlpIsTooShort));
+    }
+}
@@ -0,0 +1,231 @@
+use primitive_types_solana::U256;
+use rlp::{DecoderError, Rlp};
+
+use crate::envelope::{fixed_bytes, split_envelope, TxType};
+
+/// The fields of a signed transaction that callers act on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transaction {
+    pub tx_type: TxType,
+    /// `None` for pre-EIP-155 legacy transactions.
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    /// `None` for contract creation.
+    pub to: Option<[u8; 20]>,
+    pub value: U256,
+    pub input: Vec<u8>,
+}
+
+impl Transaction {
+    /// Decodes a transaction trie value, legacy or EIP-2718 typed.
+    pub fn decode(data: &[u8]) -> Result<Self, DecoderError> {
+        let (tx_type, payload) = split_envelope(data)?;
+        let rlp = Rlp::new(payload);
+
+        // Field count and position of `nonce` for each envelope; `to`, `value`
+        // and `input` follow the gas fields at a fixed offset from it.
+        let (item_count, nonce_index, to_index) = match tx_type {
+            TxType::Legacy => (9, 0, 3),
+            TxType::AccessList => (11, 1, 4),
+            TxType::DynamicFee => (12, 1, 5),
+            TxType::Blob => (14, 1, 5),
+            TxType::SetCode => (13, 1, 5),
+        };
+        if rlp.item_count()? != item_count {
+            return Err(DecoderError::RlpIncorrectListLen);
+        }
+
+        let chain_id = match tx_type {
+            TxType::Legacy => legacy_chain_id(rlp.val_at(6)?),
+            _ => Some(rlp.val_at(0)?),
+        };
+
+        let to = {
+            let item = rlp.at(to_index)?;
+            if item.is_empty() {
+                None
+            } else {
+                Some(fixed_bytes(&item)?)
+            }
+        };
+        if to.is_none() && matches!(tx_type, TxType::Blob | TxType::SetCode) {
+            return Err(DecoderError::Custom("missing transaction recipient"));
+        }
+
+        let value = rlp.at(to_index + 1)?.data()?;
+        if value.len() > 32 {
+            return Err(DecoderError::RlpIsTooBig);
+        }
+
+        Ok(Transaction {
+            tx_type,
+            chain_id,
+            nonce: rlp.val_at(nonce_index)?,
+            to,
+            value: U256::from_big_endian(value),
+            input: rlp.at(to_index + 2)?.data()?.to_vec(),
+        })
+    }
+}
+
+/// Chain id encoded in a legacy `v` per EIP-155; `v` of 27 or 28 predates it.
+fn legacy_chain_id(v: u64) -> Option<u64> {
+    if v >= 35 {
+        Some((v - 35) / 2)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rlp::RlpStream};
+
+    const TO: [u8; 20] = [0xab; 20];
+    const VALUE: u64 = 1_500_000_000_000_000_000;
+    const INPUT: [u8; 8] = [0xa9, 0x05, 0x9c, 0xbb, 0, 0, 0, 1];
+
+    fn encode<E: rlp::Encodable>(value: &E) -> Vec<u8> {
+        rlp::encode(value).to_vec()
+    }
+
+    /// An access list granting `TO` one storage key.
+    fn access_list() -> Vec<u8> {
+        let mut stream = RlpStream::new_list(1);
+        stream.begin_list(2).append(&TO.as_slice());
+        stream.begin_list(1).append(&[0x01; 32].as_slice());
+        stream.out().to_vec()
+    }
+
+    /// Appends a dummy `y_parity, r, s` signature to `fields` and wraps them
+    /// in the envelope of `tx_type`.
+    fn enveloped(tx_type: u8, fields: &[Vec<u8>]) -> Vec<u8> {
+        let mut stream = RlpStream::new_list(fields.len() + 3);
+        for field in fields {
+            stream.append_raw(field, 1);
+        }
+        stream
+            .append(&1u8)
+            .append(&[0x11; 32].as_slice())
+            .append(&[0x22; 32].as_slice());
+        [&[tx_type][..], &stream.out()].concat()
+    }
+
+    /// Unsigned fields of an EIP-1559 transaction, from `chain_id` to the
+    /// access list, which the blob and set-code envelopes extend.
+    fn dynamic_fee_fields() -> Vec<Vec<u8>> {
+        vec![
+            encode(&1u64),
+            encode(&7u64),
+            encode(&1_000_000_000u64),
+            encode(&30_000_000_000u64),
+            encode(&100_000u64),
+            encode(&TO.as_slice()),
+            encode(&VALUE),
+            encode(&INPUT.as_slice()),
+            access_list(),
+        ]
+    }
+
+    fn check(tx: Transaction, tx_type: TxType) {
+        assert_eq!(tx.tx_type, tx_type);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 7);
+        assert_eq!(tx.to, Some(TO));
+        assert_eq!(tx.value, U256::from(VALUE));
+        assert_eq!(tx.input, INPUT);
+    }
+
+    #[test]
+    fn eip155_example_transaction() {
+        // The signed transaction given as the example in EIP-155.
+        let raw = hex::decode(concat!(
+            "f86c098504a817c800825208943535353535353535353535353535353535353535",
+            "880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c",
+            "71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc",
+            "64214b297fb1966a3b6d83",
+        ))
+        .unwrap();
+        let tx = Transaction::decode(&raw).unwrap();
+        assert_eq!(tx.tx_type, TxType::Legacy);
+        assert_eq!(tx.chain_id, Some(1));
+        assert_eq!(tx.nonce, 9);
+        assert_eq!(tx.to, Some([0x35; 20]));
+        assert_eq!(tx.value, U256::from(1_000_000_000_000_000_000u64));
+        assert!(tx.input.is_empty());
+    }
+
+    #[test]
+    fn access_list_transaction() {
+        let fields = [
+            encode(&1u64),
+            encode(&7u64),
+            encode(&20_000_000_000u64),
+            encode(&100_000u64),
+            encode(&TO.as_slice()),
+            encode(&VALUE),
+            encode(&INPUT.as_slice()),
+            access_list(),
+        ];
+        let tx = Transaction::decode(&enveloped(0x01, &fields)).unwrap();
+        check(tx, TxType::AccessList);
+    }
+
+    #[test]
+    fn dynamic_fee_transaction() {
+        let tx = Transaction::decode(&enveloped(0x02, &dynamic_fee_fields())).unwrap();
+        check(tx, TxType::DynamicFee);
+    }
+
+    #[test]
+    fn blob_transaction() {
+        let mut fields = dynamic_fee_fields();
+        let mut hashes = RlpStream::new_list(1);
+        hashes.append(&[[0x01].as_slice(), &[0x33; 31]].concat());
+        fields.extend([encode(&1_000_000u64), hashes.out().to_vec()]);
+        let tx = Transaction::decode(&enveloped(0x03, &fields)).unwrap();
+        check(tx, TxType::Blob);
+
+        // Blob transactions cannot create contracts.
+        fields[5] = encode(&"");
+        assert_eq!(
+            Transaction::decode(&enveloped(0x03, &fields)),
+            Err(DecoderError::Custom("missing transaction recipient"))
+        );
+    }
+
+    #[test]
+    fn set_code_transaction() {
+        let mut fields = dynamic_fee_fields();
+        let mut authorizations = RlpStream::new_list(1);
+        authorizations
+            .begin_list(6)
+            .append(&1u64)
+            .append(&[0xcd; 20].as_slice())
+            .append(&0u64)
+            .append(&0u8)
+            .append(&[0x44; 32].as_slice())
+            .append(&[0x55; 32].as_slice());
+        fields.push(authorizations.out().to_vec());
+        let tx = Transaction::decode(&enveloped(0x04, &fields)).unwrap();
+        check(tx, TxType::SetCode);
+    }
+
+    #[test]
+    fn legacy_contract_creation() {
+        let mut stream = RlpStream::new_list(9);
+        stream
+            .append(&0u64)
+            .append(&20_000_000_000u64)
+            .append(&1_000_000u64)
+            .append_empty_data()
+            .append(&0u64)
+            .append(&INPUT.as_slice())
+            .append(&27u64)
+            .append(&[0x11; 32].as_slice())
+            .append(&[0x22; 32].as_slice());
+        let tx = Transaction::decode(&stream.out()).unwrap();
+        assert_eq!((tx.to, tx.chain_id), (None, None));
+        assert_eq!(tx.input, INPUT);
+    }
+}